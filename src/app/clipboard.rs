@@ -0,0 +1,91 @@
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+/// Escape sequence asking the terminal to put `text` on the clipboard (OSC 52)
+///
+/// Terminals honor it over SSH too, since it travels with the output. Inside
+/// tmux the sequence is sent a second time through tmux's passthrough, so it
+/// reaches the outer terminal with either `set-clipboard on` or
+/// `allow-passthrough on`.
+pub fn osc52(text: &str) -> String {
+    osc52_sequence(text, env::var_os("TMUX").is_some())
+}
+
+fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if !in_tmux {
+        return sequence;
+    }
+    let passthrough = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    sequence + &passthrough
+}
+
+/// Pipe `text` to a local clipboard helper such as `pbcopy` or
+/// `xclip -selection clipboard` (`clipboard_command`)
+pub fn run_helper(command_line: &str, text: &str) -> Result<(), String> {
+    let words = shlex::split(command_line)
+        .filter(|words| !words.is_empty())
+        .ok_or_else(|| format!("Invalid clipboard command: {}", command_line))?;
+    let program = &words[0];
+
+    let mut child = Command::new(program)
+        .args(&words[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+
+    // Closing stdin tells the helper the text is complete
+    let written = match child.stdin.take() {
+        Some(mut stdin) => stdin.write_all(text.as_bytes()),
+        None => Ok(()),
+    };
+    // Always reap the helper; if it exited without reading everything, its
+    // status says more than the broken pipe
+    let status = child.wait().map_err(|e| format!("{}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} failed ({})", program, status));
+    }
+    written.map_err(|e| format!("{}: {}", program, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+    }
+
+    #[test]
+    fn test_osc52_passthrough_in_tmux() {
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1b]52;c;aGk=\x07\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_helper_receives_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clip board.txt");
+        let command = format!("sh -c 'cat > \"$1\"' sh '{}'", path.display());
+
+        run_helper(&command, ".users[0]").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), ".users[0]");
+    }
+
+    #[test]
+    fn test_helper_failures() {
+        assert!(run_helper("false", "x").unwrap_err().starts_with("false failed"));
+        assert!(run_helper("/nonexistent/pbcopy", "x").unwrap_err().starts_with("/nonexistent/pbcopy:"));
+        assert!(run_helper("'unterminated", "x").unwrap_err().starts_with("Invalid clipboard command"));
+    }
+}
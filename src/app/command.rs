@@ -0,0 +1,227 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+
+use crate::query::record::RecordFormat;
+
+/// A command entered at the `:` prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `:w FILE`, `:wquery FILE`
+    Save(SaveCommand),
+    /// `:y`, `:y query`, `:y value`
+    Copy(CopyTarget),
+    /// `:record`, `:record shell`: exit printing a record of the query
+    Record(RecordFormat),
+}
+
+/// What a copy command puts on the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyTarget {
+    /// The results, uncolored (`:y`)
+    Results,
+    /// The query text (`:y query`)
+    Query,
+    /// The value on the Results pane's cursor line (`:y value`)
+    Value,
+}
+
+impl Command {
+    /// Parse a command line; see `SaveCommand::parse` for the save commands
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let name_end = line.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(line.len());
+        let (name, rest) = line.split_at(name_end);
+
+        match name {
+            "y" | "yank" => match rest.trim() {
+                "" | "results" => Ok(Command::Copy(CopyTarget::Results)),
+                "query" => Ok(Command::Copy(CopyTarget::Query)),
+                "value" => Ok(Command::Copy(CopyTarget::Value)),
+                _ => Err(format!("Usage: :{} [query|value]", name)),
+            },
+            "record" => match rest.trim() {
+                "" => Ok(Command::Record(RecordFormat::Json)),
+                format => RecordFormat::from_str(format, true)
+                    .map(Command::Record)
+                    .map_err(|_| "Usage: :record [json|shell]".to_string()),
+            },
+            _ => SaveCommand::parse(line).map(Command::Save),
+        }
+    }
+}
+
+/// What a save command writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveTarget {
+    /// The results, uncolored (`:w`)
+    Results,
+    /// The query text (`:wquery`)
+    Query,
+}
+
+/// A save command entered at the `:` prompt
+///
+/// `:w FILE` writes the results, `:wquery FILE` the query; `>> FILE` appends
+/// to the file and `!` (`:w! FILE`) overwrites it without asking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveCommand {
+    pub target: SaveTarget,
+    pub path: PathBuf,
+    /// Add to the end of the file instead of replacing it
+    pub append: bool,
+    /// Replace an existing file without confirmation
+    pub force: bool,
+}
+
+impl SaveCommand {
+    /// Parse a command line such as `w out.json`, `w! out.json`,
+    /// `w >> results.jsonl` or `wquery filter.jq`
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let name_end = line.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(line.len());
+        let (name, rest) = line.split_at(name_end);
+
+        let target = match name {
+            "w" | "write" => SaveTarget::Results,
+            "wquery" => SaveTarget::Query,
+            "" => return Err("Commands: :w FILE, :wquery FILE, :y [query|value], :record [json|shell]".to_string()),
+            name => return Err(format!("Unknown command: {}", name)),
+        };
+
+        let (force, rest) = match rest.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let rest = rest.trim();
+        let (append, path) = match rest.strip_prefix(">>") {
+            Some(path) => (true, path.trim()),
+            None => (false, rest),
+        };
+        if path.is_empty() {
+            return Err(format!("Usage: :{}[!] [>>] FILE", name));
+        }
+
+        Ok(Self {
+            target,
+            path: expand_home(path),
+            append,
+            force,
+        })
+    }
+
+    /// Whether writing would replace an existing file the user hasn't agreed to replace
+    pub fn needs_confirmation(&self) -> bool {
+        !self.append && !self.force && self.path.exists()
+    }
+
+    /// Write `content` to the file, returning the number of bytes written
+    pub fn write(&self, content: &str) -> io::Result<usize> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.append)
+            .truncate(!self.append)
+            .open(&self.path)?;
+        file.write_all(content.as_bytes())?;
+        Ok(content.len())
+    }
+
+    /// Status message after `bytes` were written
+    pub fn describe(&self, bytes: usize) -> String {
+        let verb = if self.append { "Appended" } else { "Wrote" };
+        format!("{} {} bytes to {}", verb, bytes, self.path.display())
+    }
+}
+
+/// `~/file` relative to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn save(target: SaveTarget, path: &str, append: bool, force: bool) -> SaveCommand {
+        SaveCommand {
+            target,
+            path: PathBuf::from(path),
+            append,
+            force,
+        }
+    }
+
+    #[test]
+    fn test_parse_save_commands() {
+        assert_eq!(SaveCommand::parse("w out.json"), Ok(save(SaveTarget::Results, "out.json", false, false)));
+        assert_eq!(SaveCommand::parse(" write  out.json "), Ok(save(SaveTarget::Results, "out.json", false, false)));
+        assert_eq!(SaveCommand::parse("w! out.json"), Ok(save(SaveTarget::Results, "out.json", false, true)));
+        assert_eq!(SaveCommand::parse("w >> log.jsonl"), Ok(save(SaveTarget::Results, "log.jsonl", true, false)));
+        assert_eq!(SaveCommand::parse("w>>log.jsonl"), Ok(save(SaveTarget::Results, "log.jsonl", true, false)));
+        assert_eq!(SaveCommand::parse("wquery my filter.jq"), Ok(save(SaveTarget::Query, "my filter.jq", false, false)));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_commands() {
+        assert!(SaveCommand::parse("w").unwrap_err().starts_with("Usage"));
+        assert!(SaveCommand::parse("w >>").unwrap_err().starts_with("Usage"));
+        assert_eq!(SaveCommand::parse("e file"), Err("Unknown command: e".to_string()));
+        assert!(SaveCommand::parse("").is_err());
+    }
+
+    #[test]
+    fn test_parse_copy_commands() {
+        assert_eq!(Command::parse("y"), Ok(Command::Copy(CopyTarget::Results)));
+        assert_eq!(Command::parse("yank query"), Ok(Command::Copy(CopyTarget::Query)));
+        assert_eq!(Command::parse("y value "), Ok(Command::Copy(CopyTarget::Value)));
+        assert_eq!(Command::parse("y file"), Err("Usage: :y [query|value]".to_string()));
+        assert_eq!(
+            Command::parse("w out.json"),
+            Ok(Command::Save(save(SaveTarget::Results, "out.json", false, false)))
+        );
+    }
+
+    #[test]
+    fn test_parse_record_commands() {
+        assert_eq!(Command::parse("record"), Ok(Command::Record(RecordFormat::Json)));
+        assert_eq!(Command::parse("record shell"), Ok(Command::Record(RecordFormat::Shell)));
+        assert_eq!(Command::parse("record xml"), Err("Usage: :record [json|shell]".to_string()));
+    }
+
+    #[test]
+    fn test_home_is_expanded() {
+        let command = SaveCommand::parse("w ~/out.json").unwrap();
+        if let Some(home) = env::var_os("HOME") {
+            assert_eq!(command.path, PathBuf::from(home).join("out.json"));
+        }
+    }
+
+    #[test]
+    fn test_write_and_append() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.json");
+        let path_text = path.to_str().unwrap();
+
+        let write = save(SaveTarget::Results, path_text, false, false);
+        assert!(!write.needs_confirmation());
+        assert_eq!(write.write("{}\n").unwrap(), 3);
+        assert!(write.needs_confirmation());
+
+        let append = save(SaveTarget::Results, path_text, true, false);
+        assert!(!append.needs_confirmation());
+        append.write("[]\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}\n[]\n");
+
+        write.write("1\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1\n");
+        assert_eq!(append.describe(3), format!("Appended 3 bytes to {}", path_text));
+    }
+}
@@ -1,13 +1,31 @@
 use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
-use std::io;
+use std::io::{self, Write};
+use std::time::Duration;
 use tui_textarea::CursorMove;
 
 use crate::editor::EditorMode;
+use crate::query::output::OutputOptions;
+use super::command::CopyTarget;
 use super::state::{App, Focus, OutputMode};
 
+/// How long to wait for terminal input before returning to the event loop
+const EVENT_POLL_TIMEOUT: Duration = Duration::from_millis(50);
+
 impl App {
     /// Handle events and update application state
+    ///
+    /// Waits at most `EVENT_POLL_TIMEOUT` for input (less if a debounced query
+    /// is about to be due) so the caller can keep ticking while the user is idle.
     pub fn handle_events(&mut self) -> io::Result<()> {
+        let timeout = self
+            .debouncer
+            .time_until_due()
+            .map_or(EVENT_POLL_TIMEOUT, |due| due.min(EVENT_POLL_TIMEOUT));
+
+        if !event::poll(timeout)? {
+            return Ok(());
+        }
+
         match event::read()? {
             // Check that it's a key press event to avoid duplicates
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
@@ -18,8 +36,35 @@ impl App {
         Ok(())
     }
 
+    /// Send copied text to the terminal's clipboard by writing its OSC 52
+    /// sequence to `terminal`, the output the TUI is drawn on
+    ///
+    /// Called after every tick: copies of raw results finish in the background.
+    pub fn flush_clipboard(&mut self, terminal: &mut impl Write) -> io::Result<()> {
+        if let Some(sequence) = self.clipboard_output.take() {
+            terminal.write_all(sequence.as_bytes())?;
+            terminal.flush()?;
+        }
+        Ok(())
+    }
+
     /// Handle key press events
     fn handle_key_event(&mut self, key: KeyEvent) {
+        // The last command's outcome stays up until the next key
+        self.status_message = None;
+
+        // A save waiting to overwrite a file takes the answer first
+        if self.pending_save.is_some() {
+            self.confirm_save(matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')));
+            return;
+        }
+
+        // The command line takes all keys while it is open
+        if self.command_line.is_some() {
+            self.handle_command_line_key(key);
+            return;
+        }
+
         // Try global keys first
         if self.handle_global_keys(key) {
             return; // Key was handled globally
@@ -56,6 +101,27 @@ impl App {
             return false;
         }
 
+        // Ctrl+S: Prompt for a file to save the results to
+        if key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.open_command_line("w ");
+            return true;
+        }
+
+        // Ctrl+N / Ctrl+P: Switch between input files (with --tabs)
+        if key.modifiers.contains(KeyModifiers::CONTROL) && self.sources.len() > 1 {
+            match key.code {
+                KeyCode::Char('n') => {
+                    self.next_source();
+                    return true;
+                }
+                KeyCode::Char('p') => {
+                    self.previous_source();
+                    return true;
+                }
+                _ => {}
+            }
+        }
+
         // Shift+Tab: Switch focus between panes
         if key.code == KeyCode::BackTab {
             self.focus = match self.focus {
@@ -80,9 +146,9 @@ impl App {
             return true;
         }
 
-        // Enter: Exit and output filtered results
+        // Enter: Exit and output filtered results (or a record of them with --record)
         if key.code == KeyCode::Enter {
-            self.output_mode = Some(OutputMode::Results);
+            self.output_mode = Some(self.record_format.map_or(OutputMode::Results, OutputMode::Record));
             self.should_quit = true;
             return true;
         }
@@ -90,6 +156,26 @@ impl App {
         false // Key not handled
     }
 
+    /// Handle keys while the `:` command line is open
+    fn handle_command_line_key(&mut self, key: KeyEvent) {
+        let Some(line) = self.command_line.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.command_line = None,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.command_line = None,
+            KeyCode::Enter => {
+                if let Some(line) = self.command_line.take() {
+                    self.run_command(&line);
+                }
+            }
+            // Deleting past the start closes the prompt, as in vim
+            KeyCode::Backspace if line.pop().is_none() => self.command_line = None,
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+
     /// Handle keys when Input field is focused
     fn handle_input_field_key(&mut self, key: KeyEvent) {
         // Handle ESC - close autocomplete or switch to Normal mode
@@ -130,18 +216,9 @@ impl App {
         // Use textarea's built-in input handling
         let content_changed = self.textarea.input(key);
 
-        // Execute query on every keystroke that changes content
+        // Execute query once typing pauses
         if content_changed {
-            let query = self.textarea.lines()[0].as_ref();
-            self.query_result = self.executor.execute(query);
-
-            // Cache successful results
-            if let Ok(result) = &self.query_result {
-                self.last_successful_result = Some(result.clone());
-            }
-
-            // Reset scroll when query changes
-            self.results_scroll = 0;
+            self.schedule_query();
         }
 
         // Update autocomplete suggestions after any input
@@ -237,6 +314,16 @@ impl App {
                 self.textarea.start_selection();
             }
 
+            // Command line (:w FILE, :y, ...)
+            KeyCode::Char(':') => {
+                self.open_command_line("");
+            }
+
+            // Copy the query to the clipboard
+            KeyCode::Char('Y') => {
+                self.copy(CopyTarget::Query);
+            }
+
             // Undo/Redo
             KeyCode::Char('u') => {
                 // u - Undo
@@ -342,19 +429,6 @@ impl App {
         }
     }
 
-    /// Execute current query and update results
-    fn execute_query(&mut self) {
-        let query = self.textarea.lines()[0].as_ref();
-        self.query_result = self.executor.execute(query);
-
-        // Cache successful results
-        if let Ok(result) = &self.query_result {
-            self.last_successful_result = Some(result.clone());
-        }
-
-        self.results_scroll = 0;
-    }
-
     /// Handle keys when Results pane is focused
     fn handle_results_pane_key(&mut self, key: KeyEvent) {
         match key.code {
@@ -374,6 +448,55 @@ impl App {
                 self.results_scroll = self.results_scroll.saturating_add(10);
             }
 
+            // Toggle following the end of the results
+            KeyCode::Char('f') => {
+                self.toggle_auto_scroll();
+            }
+
+            // Command line (:w FILE, :y, ...)
+            KeyCode::Char(':') => {
+                self.open_command_line("");
+            }
+
+            // Copy the value on the top line / all results to the clipboard
+            KeyCode::Char('y') => {
+                self.copy(CopyTarget::Value);
+            }
+            KeyCode::Char('Y') => {
+                self.copy(CopyTarget::Results);
+            }
+
+            // Output options: raw strings, compact, tab indent, sorted keys, format
+            KeyCode::Char('r') => {
+                let output = self.jq_options.output;
+                self.set_output_options(OutputOptions { raw: !output.raw, ..output });
+            }
+            KeyCode::Char('c') => {
+                let output = self.jq_options.output;
+                self.set_output_options(OutputOptions {
+                    compact: !output.compact,
+                    ..output
+                });
+            }
+            KeyCode::Char('t') => {
+                let output = self.jq_options.output;
+                self.set_output_options(OutputOptions { tab: !output.tab, ..output });
+            }
+            KeyCode::Char('S') => {
+                let output = self.jq_options.output;
+                self.set_output_options(OutputOptions {
+                    sort_keys: !output.sort_keys,
+                    ..output
+                });
+            }
+            KeyCode::Char('o') => {
+                let output = self.jq_options.output;
+                self.set_output_options(OutputOptions {
+                    format: output.format.next(),
+                    ..output
+                });
+            }
+
             // Jump to top
             KeyCode::Home | KeyCode::Char('g') => {
                 self.results_scroll = 0;
@@ -414,7 +537,11 @@ impl App {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::clipboard;
     use crate::autocomplete::{Suggestion, SuggestionType};
+    use crate::config::Config;
+    use crate::query::output::OutputFormat;
+    use crate::query::record::RecordFormat;
 
     // Test fixture data
     const TEST_JSON: &str = r#"{"name": "test", "age": 30, "city": "NYC"}"#;
@@ -431,7 +558,7 @@ mod tests {
 
     // Helper to set up an app with text in the query field
     fn app_with_query(query: &str) -> App {
-        let mut app = App::new(TEST_JSON.to_string());
+        let mut app = App::new(vec![TEST_JSON.to_string().into()], &Config::default());
         app.textarea.insert_str(query);
         app
     }
@@ -1073,7 +1200,7 @@ mod tests {
     #[test]
     fn test_capital_g_jumps_to_bottom() {
         let json = r#"{"line1": 1, "line2": 2, "line3": 3}"#;
-        let mut app = App::new(json.to_string());
+        let mut app = App::new(vec![json.to_string().into()], &Config::default());
         app.textarea.insert_str(".");
         app.focus = Focus::ResultsPane;
         app.results_scroll = 0;
@@ -1220,6 +1347,29 @@ mod tests {
         assert!(app.should_quit);
     }
 
+    #[test]
+    fn test_enter_with_record_format_sets_record_output_mode() {
+        let mut app = app_with_query(".");
+        app.record_format = Some(RecordFormat::Shell);
+
+        app.handle_key_event(key(KeyCode::Enter));
+
+        assert_eq!(app.output_mode, Some(OutputMode::Record(RecordFormat::Shell)));
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_record_command_exits_with_record() {
+        let mut app = app_with_query(".name");
+        app.focus = Focus::ResultsPane;
+
+        app.handle_key_event(key(KeyCode::Char(':')));
+        type_command(&mut app, "record");
+
+        assert_eq!(app.output_mode, Some(OutputMode::Record(RecordFormat::Json)));
+        assert!(app.should_quit);
+    }
+
     #[test]
     fn test_shift_tab_switches_focus_to_results() {
         let mut app = app_with_query(".");
@@ -1274,4 +1424,232 @@ mod tests {
         // Scroll should be reset when query changes
         assert_eq!(app.results_scroll, 0);
     }
+
+    #[test]
+    fn test_typing_debounces_query_execution() {
+        let mut app = app_with_query("");
+        app.editor_mode = EditorMode::Insert;
+
+        app.handle_key_event(key(KeyCode::Char('.')));
+
+        // Query is scheduled, not spawned yet
+        assert!(app.debouncer.is_pending());
+        assert!(!app.query_worker.is_running());
+    }
+
+    #[test]
+    fn test_accepting_autocomplete_executes_immediately() {
+        let mut app = app_with_query(".na");
+        app.editor_mode = EditorMode::Insert;
+        app.debouncer.schedule();
+
+        app.autocomplete
+            .update_suggestions(vec![Suggestion::new(".name", SuggestionType::Field)]);
+        app.handle_key_event(key(KeyCode::Tab));
+
+        assert!(!app.debouncer.is_pending());
+        assert!(app.query_worker.is_running());
+    }
+
+    #[test]
+    fn test_ctrl_n_and_ctrl_p_switch_input_files() {
+        use crate::input::source::InputSource;
+
+        let sources = vec![
+            InputSource::new("a.json", TEST_JSON.to_string()),
+            InputSource::new("b.json", TEST_JSON.to_string()),
+        ];
+        let mut app = App::new(sources, &Config::default());
+
+        app.handle_key_event(key_with_mods(KeyCode::Char('n'), KeyModifiers::CONTROL));
+        assert_eq!(app.active_source, 1);
+        assert_eq!(app.query(), "");
+
+        app.handle_key_event(key_with_mods(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        assert_eq!(app.active_source, 0);
+    }
+
+    #[test]
+    fn test_f_toggles_auto_scroll_in_results_pane() {
+        let mut app = app_with_query(".");
+        app.focus = Focus::ResultsPane;
+
+        app.handle_key_event(key(KeyCode::Char('f')));
+        assert!(app.auto_scroll);
+
+        app.handle_key_event(key(KeyCode::Char('f')));
+        assert!(!app.auto_scroll);
+    }
+
+    #[test]
+    fn test_output_options_toggle_in_results_pane() {
+        let mut app = app_with_query(".");
+        app.focus = Focus::ResultsPane;
+
+        app.handle_key_event(key(KeyCode::Char('r')));
+        app.handle_key_event(key(KeyCode::Char('c')));
+        app.handle_key_event(key(KeyCode::Char('S')));
+        app.handle_key_event(key(KeyCode::Char('o')));
+        assert_eq!(
+            app.jq_options.output,
+            OutputOptions {
+                raw: true,
+                compact: true,
+                tab: false,
+                sort_keys: true,
+                monochrome: false,
+                format: OutputFormat::Yaml,
+            }
+        );
+
+        app.handle_key_event(key(KeyCode::Char('c')));
+        assert!(!app.jq_options.output.compact);
+    }
+
+    #[test]
+    fn test_output_keys_type_into_query_field() {
+        let mut app = app_with_query("");
+
+        app.handle_key_event(key(KeyCode::Char('c')));
+        assert_eq!(app.query(), "c");
+        assert_eq!(app.jq_options.output, OutputOptions::default());
+    }
+
+    // Helper to type text followed by Enter into the open command line
+    fn type_command(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle_key_event(key(KeyCode::Char(c)));
+        }
+        app.handle_key_event(key(KeyCode::Enter));
+    }
+
+    #[test]
+    fn test_ctrl_s_saves_results_without_quitting() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.json");
+        let mut app = app_with_query(".");
+
+        app.handle_key_event(key_with_mods(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert_eq!(app.command_line.as_deref(), Some("w "));
+
+        type_command(&mut app, path.to_str().unwrap());
+        assert!(app.command_line.is_none());
+        assert!(!app.should_quit);
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("\"name\": \"test\""));
+        assert!(!saved.contains('\x1b'));
+        assert_eq!(
+            app.status_message,
+            Some(format!("Wrote {} bytes to {}", saved.len(), path.display()))
+        );
+    }
+
+    #[test]
+    fn test_overwrite_asks_for_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.json");
+        std::fs::write(&path, "old\n").unwrap();
+        let mut app = app_with_query(".");
+        app.editor_mode = EditorMode::Normal;
+
+        app.handle_key_event(key(KeyCode::Char(':')));
+        type_command(&mut app, &format!("w {}", path.display()));
+        assert!(app.pending_save.is_some());
+
+        app.handle_key_event(key(KeyCode::Char('n')));
+        assert!(app.pending_save.is_none());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old\n");
+
+        app.handle_key_event(key(KeyCode::Char(':')));
+        type_command(&mut app, &format!("w {}", path.display()));
+        app.handle_key_event(key(KeyCode::Char('y')));
+        assert!(std::fs::read_to_string(&path).unwrap().contains("NYC"));
+    }
+
+    #[test]
+    fn test_append_and_save_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.txt");
+        std::fs::write(&path, "first\n").unwrap();
+        let mut app = app_with_query(".city");
+        app.focus = Focus::ResultsPane;
+
+        app.handle_key_event(key(KeyCode::Char(':')));
+        type_command(&mut app, &format!("wquery >> {}", path.display()));
+
+        assert!(app.pending_save.is_none());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\n.city\n");
+        assert!(app.status_message.as_ref().unwrap().starts_with("Appended 6 bytes"));
+    }
+
+    #[test]
+    fn test_command_line_escape_and_errors() {
+        let mut app = app_with_query(".");
+        app.focus = Focus::ResultsPane;
+
+        app.handle_key_event(key(KeyCode::Char(':')));
+        app.handle_key_event(key(KeyCode::Char('q')));
+        app.handle_key_event(key(KeyCode::Esc));
+        assert!(app.command_line.is_none());
+        assert!(!app.should_quit);
+
+        app.handle_key_event(key(KeyCode::Char(':')));
+        type_command(&mut app, "x file");
+        assert_eq!(app.status_message.as_deref(), Some("Unknown command: x"));
+
+        // The message is cleared by the next key
+        app.handle_key_event(key(KeyCode::Char('j')));
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn test_capital_y_copies_query_in_normal_mode() {
+        let mut app = app_with_query(".name");
+        app.editor_mode = EditorMode::Normal;
+
+        app.handle_key_event(key(KeyCode::Char('Y')));
+
+        assert_eq!(app.clipboard_output, Some(clipboard::osc52(".name")));
+        assert_eq!(app.status_message.as_deref(), Some("Copied query (5 bytes)"));
+        assert_eq!(app.query(), ".name");
+    }
+
+    #[test]
+    fn test_y_copies_value_on_top_line_of_results() {
+        let mut app = app_with_query(".");
+        app.focus = Focus::ResultsPane;
+        app.results_scroll = 1;
+
+        app.handle_key_event(key(KeyCode::Char('y')));
+        assert_eq!(app.clipboard_output, Some(clipboard::osc52("test")));
+
+        app.results_scroll = 0;
+        app.handle_key_event(key(KeyCode::Char('y')));
+        let value = "{\n  \"name\": \"test\",\n  \"age\": 30,\n  \"city\": \"NYC\"\n}";
+        assert_eq!(app.clipboard_output, Some(clipboard::osc52(value)));
+
+        // Whole results, uncolored
+        app.handle_key_event(key(KeyCode::Char('Y')));
+        assert_eq!(app.clipboard_output, Some(clipboard::osc52(value)));
+        assert_eq!(app.status_message, Some(format!("Copied results ({} bytes)", value.len())));
+    }
+
+    #[test]
+    fn test_copy_command_runs_clipboard_helper() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clipboard.txt");
+        let mut app = app_with_query(".age");
+        app.clipboard_command = Some(format!("sh -c 'cat > {}'", path.display()));
+
+        app.open_command_line("");
+        type_command(&mut app, "y query");
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), ".age");
+        assert_eq!(app.clipboard_output, Some(clipboard::osc52(".age")));
+
+        app.clipboard_command = Some("false".to_string());
+        app.copy(CopyTarget::Query);
+        assert_eq!(app.status_message.as_deref(), Some("Copied query (4 bytes); false failed (exit status: 1)"));
+    }
 }
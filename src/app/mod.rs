@@ -1,3 +1,5 @@
+mod clipboard;
+mod command;
 mod state;
 mod events;
 mod render;
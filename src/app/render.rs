@@ -9,6 +9,7 @@ use ratatui::{
 
 use crate::autocomplete::SuggestionType;
 use crate::editor::EditorMode;
+use crate::query::output::OutputOptions;
 use crate::syntax::JqHighlighter;
 use super::state::{App, Focus};
 
@@ -23,17 +24,25 @@ const TYPE_LABEL_SPACING: usize = 3;
 impl App {
     /// Render the UI
     pub fn render(&mut self, frame: &mut Frame) {
-        // Split the terminal into three areas: results, input, and help
+        // Split the terminal into four areas: input error banner, results, input, and help
+        let banner_height = if self.input_error.is_some() { 1 } else { 0 };
         let layout = Layout::vertical([
+            Constraint::Length(banner_height), // Input error banner (--watch/--follow), usually hidden
             Constraint::Min(3),      // Results pane takes most of the space
             Constraint::Length(3),   // Input field is fixed 3 lines
             Constraint::Length(1),   // Help line at bottom
         ])
         .split(frame.area());
 
-        let results_area = layout[0];
-        let input_area = layout[1];
-        let help_area = layout[2];
+        let banner_area = layout[0];
+        let results_area = layout[1];
+        let input_area = layout[2];
+        let help_area = layout[3];
+
+        // Render reload banner
+        if let Some(error) = &self.input_error {
+            self.render_input_banner(frame, banner_area, error);
+        }
 
         // Render results pane
         self.render_results_pane(frame, results_area);
@@ -50,6 +59,18 @@ impl App {
         }
     }
 
+    /// Render the banner shown while the input can't be (re)loaded
+    fn render_input_banner(&self, frame: &mut Frame, area: Rect, error: &str) {
+        // Only the first line fits; multi-line errors (e.g. TOML) start with the location
+        let first_line = error.lines().next().unwrap_or_default();
+        let text = format!(" {}", first_line);
+
+        let banner = Paragraph::new(text)
+            .style(Style::default().fg(Color::Black).bg(Color::Yellow));
+
+        frame.render_widget(banner, area);
+    }
+
     /// Render the input field (bottom)
     fn render_input_field(&mut self, frame: &mut Frame, area: ratatui::layout::Rect) {
         // Choose color based on mode
@@ -154,7 +175,7 @@ impl App {
 
                 let block = Block::default()
                     .borders(Borders::ALL)
-                    .title(" Results ")
+                    .title(self.results_title("Results"))
                     .border_style(Style::default().fg(border_color));
 
                 // Parse jq's ANSI color codes into Ratatui Text
@@ -169,6 +190,7 @@ impl App {
                     .scroll((self.results_scroll, 0));
 
                 frame.render_widget(content, area);
+                self.render_cursor_line(frame, area);
             }
             Err(error) => {
                 // Split the area: error at top, last successful result below
@@ -204,7 +226,7 @@ impl App {
                     // Render last successful result section
                     let results_block = Block::default()
                         .borders(Borders::ALL)
-                        .title(" Results (last valid query) ")
+                        .title(self.results_title("Results (last valid query)"))
                         .border_style(Style::default().fg(border_color));
 
                     // Parse cached result with colors
@@ -219,6 +241,7 @@ impl App {
                         .scroll((self.results_scroll, 0));
 
                     frame.render_widget(results_widget, results_area);
+                    self.render_cursor_line(frame, results_area);
                 } else {
                     // No cached result, just show error (fallback to original behavior)
                     self.results_viewport_height = area.height.saturating_sub(2);
@@ -239,16 +262,93 @@ impl App {
         }
     }
 
-    /// Render the help line (bottom)
+    /// Build the Results block title with the active input file and output
+    /// options, marking queries still in flight
+    fn results_title(&self, label: &str) -> String {
+        let mut title = format!(" {}", label);
+
+        if let Some(name) = &self.source().name {
+            if self.sources.len() > 1 {
+                title.push_str(&format!(" [{} {}/{}]", name, self.active_source + 1, self.sources.len()));
+            } else {
+                title.push_str(&format!(" [{}]", name));
+            }
+        }
+
+        if let Some(stream) = &self.input_stream {
+            let state = if stream.is_open() { "following" } else { "ended" };
+            title.push_str(&format!(" ({}, {} records)", state, stream.record_count()));
+        }
+
+        if self.jq_options.output != OutputOptions::default() {
+            title.push_str(&format!(" [{}]", self.jq_options.output));
+        }
+
+        if self.auto_scroll {
+            title.push_str(" [auto-scroll]");
+        }
+
+        if self.is_query_running() {
+            title.push_str(" (running…)");
+        }
+
+        title.push(' ');
+        title
+    }
+
+    /// Render the help line (bottom), led by any notes about how the input was read
     fn render_help_line(&self, frame: &mut Frame, area: ratatui::layout::Rect) {
-        let help_text = " Tab: Autocomplete | Shift+Tab: Switch Focus | Enter: Exit with Results | Shift+Enter: Exit with Query | q: Quit";
+        // The command line and the overwrite question replace the help text
+        if let Some(line) = &self.command_line {
+            let prompt = format!(":{}", line);
+            let cursor_x = area.x.saturating_add(prompt.chars().count() as u16);
+            frame.render_widget(Paragraph::new(prompt), area);
+            frame.set_cursor_position((cursor_x.min(area.right().saturating_sub(1)), area.y));
+            return;
+        }
+        if let Some((command, _)) = &self.pending_save {
+            let question = format!(" {} exists. Overwrite? (y/n)", command.path.display());
+            frame.render_widget(
+                Paragraph::new(question).style(Style::default().fg(Color::Yellow)),
+                area,
+            );
+            return;
+        }
 
-        let help = Paragraph::new(help_text)
-            .style(Style::default().fg(Color::DarkGray));
+        let mut help_text = String::from(" Tab: Autocomplete | Shift+Tab: Switch Focus");
+        if self.sources.len() > 1 {
+            help_text.push_str(" | Ctrl+N/P: Next/Prev File");
+        }
+        help_text.push_str(" | Ctrl+S: Save | Enter: Exit with Results | Shift+Enter: Exit with Query | q: Quit");
+        help_text.push_str(&format!(" | {}", self.engine.dialect()));
+
+        let mut spans = Vec::new();
+        if let Some(message) = &self.status_message {
+            spans.push(Span::styled(format!(" {} ", message), Style::default().fg(Color::Cyan)));
+        }
+        for warning in &self.source().warnings {
+            spans.push(Span::styled(format!(" ⚠ {} ", warning), Style::default().fg(Color::Yellow)));
+        }
+        spans.push(Span::styled(help_text, Style::default().fg(Color::DarkGray)));
+
+        let help = Paragraph::new(Line::from(spans));
 
         frame.render_widget(help, area);
     }
 
+    /// Highlight the top visible line of the results while the Results pane is
+    /// focused: it is the cursor line whose value `y` copies
+    fn render_cursor_line(&self, frame: &mut Frame, area: Rect) {
+        if self.focus != Focus::ResultsPane || self.results_scroll >= self.results_line_count() {
+            return;
+        }
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        if inner.height > 0 {
+            let line = Rect { height: 1, ..inner };
+            frame.buffer_mut().set_style(line, Style::default().add_modifier(Modifier::REVERSED));
+        }
+    }
+
     /// Render the autocomplete popup above the input field
     fn render_autocomplete_popup(&self, frame: &mut Frame, input_area: Rect) {
         let suggestions = self.autocomplete.suggestions();
@@ -275,7 +375,7 @@ impl App {
                         }
                     }
                     _ => {
-                        // Other types: "[fn]", "[op]", "[pat]"
+                        // Other types: "[fn]", "[op]", "[pat]", "[var]"
                         s.suggestion_type.to_string().len() + 2 // "[]" wrapping
                     }
                 };
@@ -316,6 +416,7 @@ impl App {
                     SuggestionType::Field => Color::Cyan,
                     SuggestionType::Operator => Color::Magenta,
                     SuggestionType::Pattern => Color::Green,
+                    SuggestionType::Variable => Color::LightRed,
                 };
 
                 let type_label = match &suggestion.suggestion_type {
@@ -4,10 +4,27 @@ use ratatui::{
 };
 use tui_textarea::TextArea;
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::autocomplete::{AutocompleteState, get_suggestions};
 use crate::autocomplete::json_analyzer::JsonAnalyzer;
+use crate::config::Config;
 use crate::editor::EditorMode;
-use crate::query::executor::JqExecutor;
+use crate::input::data::InputData;
+use crate::input::source::InputSource;
+use crate::input::stream::InputStream;
+use crate::input::watcher::InputWatcher;
+use crate::query::debouncer::Debouncer;
+use crate::query::engine::QueryEngine;
+use crate::query::executor::{ExecutionLimits, JqExecutor};
+use crate::query::ansi;
+use crate::query::options::JqOptions;
+use crate::query::output::{self, OutputFormat, OutputOptions};
+use crate::query::record::RecordFormat;
+use crate::query::worker::QueryWorker;
+use super::clipboard;
+use super::command::{Command, CopyTarget, SaveCommand, SaveTarget};
 
 // Autocomplete performance constants
 const MIN_CHARS_FOR_AUTOCOMPLETE: usize = 1;
@@ -24,12 +41,21 @@ pub enum Focus {
 pub enum OutputMode {
     Results, // Output filtered JSON results (Enter)
     Query,   // Output query string only (Shift+Enter)
+    Record(RecordFormat), // Output query, options, inputs and result (:record, Enter with --record)
+}
+
+/// What uncolored results are wanted for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlainResultUse {
+    Copy(CopyTarget),
+    Save(SaveCommand),
 }
 
 /// Application state
 pub struct App {
     pub textarea: TextArea<'static>,
-    pub executor: JqExecutor,
+    pub query_worker: QueryWorker,
+    pub debouncer: Debouncer,
     pub query_result: Result<String, String>,
     pub last_successful_result: Option<String>,
     pub focus: Focus,
@@ -40,11 +66,47 @@ pub struct App {
     pub should_quit: bool,
     pub autocomplete: AutocompleteState,
     pub json_analyzer: JsonAnalyzer,
+    /// Variables defined by jq options (`$name`), offered by autocomplete
+    pub variables: Vec<String>,
+    /// Inputs to query (one per file with `--tabs`, otherwise a single stream)
+    pub sources: Vec<InputSource>,
+    /// Index into `sources` of the input queries run against
+    pub active_source: usize,
+    /// Engine, options and limits for executors built when switching inputs
+    pub engine: Arc<dyn QueryEngine>,
+    pub jq_options: JqOptions,
+    pub execution_limits: ExecutionLimits,
+    pub cache_budget: usize,
+    /// Re-reads the input files when they change (`--watch`)
+    pub input_watcher: Option<InputWatcher>,
+    /// Banner for a failed reload or a broken input stream; the previous
+    /// data stays loaded meanwhile
+    pub input_error: Option<String>,
+    /// Records streamed from stdin (`--follow`)
+    pub input_stream: Option<InputStream>,
+    /// Keep the Results pane scrolled to the end as results change
+    pub auto_scroll: bool,
+    /// The `:` command line while it is open
+    pub command_line: Option<String>,
+    /// A save waiting for confirmation to overwrite an existing file, with
+    /// the content to write
+    pub pending_save: Option<(SaveCommand, String)>,
+    /// Run of the query without colors for a copy or save of raw output,
+    /// with what to do with the result
+    pub plain_run: Option<(QueryWorker, PlainResultUse)>,
+    /// Outcome of the last command, shown in the help line until the next key
+    pub status_message: Option<String>,
+    /// Local clipboard helper also given copied text (`clipboard_command`)
+    pub clipboard_command: Option<String>,
+    /// OSC 52 sequence of the last copy, waiting to be written to the terminal
+    pub clipboard_output: Option<String>,
+    /// Exit with a record of the query instead of the results (`--record`)
+    pub record_format: Option<RecordFormat>,
 }
 
 impl App {
-    /// Create a new App instance with JSON input
-    pub fn new(json_input: String) -> Self {
+    /// Create a new App instance querying the first of `sources` (must not be empty)
+    pub fn new(sources: Vec<InputSource>, config: &Config) -> Self {
         // Create textarea for query input
         let mut textarea = TextArea::default();
 
@@ -59,22 +121,30 @@ impl App {
         // Remove default underline from cursor line
         textarea.set_cursor_line_style(Style::default());
 
-        // Create JQ executor
-        let executor = JqExecutor::new(json_input.clone());
+        // Create JQ executor, guarded against runaway queries
+        let json_input = &sources[0].json_input;
+        let engine = config.query_engine();
+        let executor = JqExecutor::new(json_input.clone())
+            .with_engine(engine.clone())
+            .with_options(config.jq_options.clone())
+            .with_limits(config.execution_limits())
+            .with_cache(config.cache_budget());
 
         // Initial result text on startup
-        let query_result = executor.execute(".");
+        let query_result = executor.execute(".").map_err(|e| e.to_string());
 
         // Cache the initial successful result
         let last_successful_result = query_result.as_ref().ok().cloned();
 
-        // Initialize JSON analyzer with the input JSON
-        let mut json_analyzer = JsonAnalyzer::new();
-        let _ = json_analyzer.analyze(&json_input);
+        // Subsequent queries run in the background
+        let query_worker = QueryWorker::new(executor);
+
+        let json_analyzer = analyze_input(json_input, &config.jq_options);
 
         Self {
             textarea,
-            executor,
+            query_worker,
+            debouncer: Debouncer::new(Duration::from_millis(config.debounce_ms)),
             query_result,
             last_successful_result,
             focus: Focus::InputField, // Start with input field focused
@@ -85,7 +155,332 @@ impl App {
             should_quit: false,
             autocomplete: AutocompleteState::new(),
             json_analyzer,
+            variables: config.jq_options.variable_names(),
+            sources,
+            active_source: 0,
+            engine,
+            jq_options: config.jq_options.clone(),
+            execution_limits: config.execution_limits(),
+            cache_budget: config.cache_budget(),
+            input_watcher: None,
+            input_error: None,
+            input_stream: None,
+            auto_scroll: false,
+            command_line: None,
+            pending_save: None,
+            plain_run: None,
+            status_message: None,
+            clipboard_command: config.clipboard_command.clone(),
+            clipboard_output: None,
+            record_format: config.record,
+        }
+    }
+
+    /// Reload the inputs whenever they change on disk
+    pub fn with_input_watcher(mut self, watcher: InputWatcher) -> Self {
+        self.input_watcher = Some(watcher);
+        self
+    }
+
+    /// Query records as they stream in, scrolled to the newest output
+    pub fn with_input_stream(mut self, stream: InputStream) -> Self {
+        self.input_stream = Some(stream);
+        self.auto_scroll = true;
+        self
+    }
+
+    /// The input queries currently run against
+    pub fn source(&self) -> &InputSource {
+        &self.sources[self.active_source]
+    }
+
+    /// Run the query against another input and rebuild autocomplete for it
+    pub fn select_source(&mut self, index: usize) {
+        if index == self.active_source || index >= self.sources.len() {
+            return;
+        }
+        self.active_source = index;
+        self.load_active_source();
+
+        // The previous file's output is no fallback for this one's errors
+        self.last_successful_result = None;
+        self.execute_query();
+    }
+
+    /// Swap in freshly read inputs, keeping the query, cursor, scroll and mode
+    pub fn reload_sources(&mut self, sources: Vec<InputSource>) {
+        self.sources = sources;
+        self.active_source = self.active_source.min(self.sources.len() - 1);
+        self.load_active_source();
+
+        self.debouncer.cancel();
+        let query = self.query().to_string();
+        self.query_worker.submit(&query);
+    }
+
+    /// Build a fresh executor and field analysis for the active input
+    fn load_active_source(&mut self) {
+        self.rebuild_executor();
+        self.json_analyzer = analyze_input(&self.source().json_input, &self.jq_options);
+    }
+
+    /// Build a fresh executor (and result cache) for the active input and options
+    fn rebuild_executor(&mut self) {
+        let executor = JqExecutor::new(self.source().json_input.clone())
+            .with_engine(self.engine.clone())
+            .with_options(self.jq_options.clone())
+            .with_limits(self.execution_limits)
+            .with_cache(self.cache_budget);
+        // Dropping the old worker cancels whatever it was still running
+        self.query_worker = QueryWorker::new(executor);
+    }
+
+    /// Change how results are printed and re-run the query to show them that way;
+    /// exiting with results uses the same options
+    pub fn set_output_options(&mut self, output: OutputOptions) {
+        if output == self.jq_options.output {
+            return;
+        }
+        self.jq_options.output = output;
+        self.rebuild_executor();
+
+        // Output in the previous format is no fallback for errors in this one
+        self.last_successful_result = None;
+        self.execute_query();
+    }
+
+    /// Pick up changed input files (`--watch`)
+    ///
+    /// Invalid content is reported in `input_error` and the last good data
+    /// stays loaded until the file is fixed.
+    fn poll_input_watcher(&mut self) {
+        let Some(reloaded) = self.input_watcher.as_mut().and_then(InputWatcher::poll) else {
+            return;
+        };
+
+        match reloaded {
+            Ok(sources) => {
+                self.input_error = None;
+                self.reload_sources(sources);
+            }
+            Err(e) => {
+                self.input_error = Some(format!("Reload failed, showing last good data: {}", e));
+            }
+        }
+    }
+
+    /// Append records that streamed in (`--follow`) and re-run the query
+    fn poll_input_stream(&mut self) {
+        let Some(stream) = self.input_stream.as_mut() else {
+            return;
+        };
+        stream.receive();
+
+        // Let the running query finish first; restarting it for every record
+        // of a fast stream would never show a result
+        let update = if self.query_worker.is_running() {
+            None
+        } else {
+            stream.take_update()
+        };
+        let error = stream.take_error();
+
+        if let Some(json_input) = update {
+            let mut source = InputSource::from(json_input);
+            source.name = self.source().name.clone();
+            self.reload_sources(vec![source]);
+        }
+        if let Some(e) = error {
+            self.input_error = Some(format!("Input stream stopped: {}", e));
+        }
+    }
+
+    /// Toggle following the end of the results
+    pub fn toggle_auto_scroll(&mut self) {
+        self.auto_scroll = !self.auto_scroll;
+        if self.auto_scroll {
+            self.results_scroll = self.max_scroll();
+        }
+    }
+
+    /// Open the `:` command line with `text` already typed
+    pub fn open_command_line(&mut self, text: &str) {
+        self.autocomplete.hide();
+        self.command_line = Some(text.to_string());
+    }
+
+    /// Run a line entered at the `:` prompt
+    ///
+    /// The outcome is reported in `status_message`.
+    pub fn run_command(&mut self, line: &str) {
+        match Command::parse(line) {
+            Ok(Command::Save(command)) => self.start_save(command),
+            Ok(Command::Copy(target)) => self.copy(target),
+            Ok(Command::Record(format)) => {
+                self.output_mode = Some(OutputMode::Record(format));
+                self.should_quit = true;
+            }
+            Err(e) => self.status_message = Some(e),
+        }
+    }
+
+    /// Put the query, the results or the value on the Results cursor line
+    /// (the top visible line) on the clipboard
+    ///
+    /// The OSC 52 sequence is left in `clipboard_output` for the event loop to
+    /// write; a configured helper command gets the text right away.
+    pub fn copy(&mut self, target: CopyTarget) {
+        match target {
+            CopyTarget::Query => self.copy_text("query", self.query().to_string()),
+            target => self.request_plain_result(PlainResultUse::Copy(target)),
+        }
+    }
+
+    /// Copy the results or the value at the cursor from the uncolored results
+    fn copy_result(&mut self, target: CopyTarget, result: Result<String, String>) {
+        let copied = result.and_then(|result| match target {
+            CopyTarget::Value => self.value_at_cursor(&result).map(|value| ("value", value)),
+            _ => Ok(("results", result.trim_end_matches('\n').to_string())),
+        });
+        match copied {
+            Ok((name, text)) => self.copy_text(name, text),
+            Err(e) => self.status_message = Some(e),
+        }
+    }
+
+    fn copy_text(&mut self, name: &str, text: String) {
+        self.clipboard_output = Some(clipboard::osc52(&text));
+        let mut message = format!("Copied {} ({} bytes)", name, text.len());
+        if let Some(command) = &self.clipboard_command
+            && let Err(e) = clipboard::run_helper(command, &text)
+        {
+            message.push_str(&format!("; {}", e));
+        }
+        self.status_message = Some(message);
+    }
+
+    /// The value starting on the top visible line of the results, or the line
+    /// itself for output that isn't JSON
+    fn value_at_cursor(&self, result: &str) -> Result<String, String> {
+        let line = self.results_scroll as usize;
+        let value = if self.jq_options.output.format == OutputFormat::Json {
+            output::value_at_line(result, line)
+        } else {
+            None
+        };
+        value
+            .or_else(|| result.lines().nth(line).map(|text| text.trim().to_string()))
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| "Nothing to copy on this line".to_string())
+    }
+
+    /// Save the results or the query, asking first before replacing a file
+    /// (see `confirm_save`)
+    fn start_save(&mut self, command: SaveCommand) {
+        match command.target {
+            SaveTarget::Results => self.request_plain_result(PlainResultUse::Save(command)),
+            SaveTarget::Query => {
+                let content = format!("{}\n", self.query());
+                self.save_content(command, content);
+            }
+        }
+    }
+
+    fn save_content(&mut self, command: SaveCommand, content: String) {
+        if command.needs_confirmation() {
+            self.pending_save = Some((command, content));
+        } else {
+            self.save(&command, &content);
+        }
+    }
+
+    /// Answer the overwrite question of a pending save
+    pub fn confirm_save(&mut self, overwrite: bool) {
+        let Some((command, content)) = self.pending_save.take() else {
+            return;
+        };
+        if overwrite {
+            self.save(&command, &content);
+        } else {
+            self.status_message = Some(format!("Not saved: {} exists", command.path.display()));
+        }
+    }
+
+    fn save(&mut self, command: &SaveCommand, content: &str) {
+        self.status_message = Some(match command.write(content) {
+            Ok(bytes) => command.describe(bytes),
+            Err(e) => format!("Cannot write {}: {}", command.path.display(), e),
+        });
+    }
+
+    /// Get the shown result without colors, as exiting with results would
+    /// print it, and copy or save it
+    ///
+    /// Raw strings may contain escape sequences of their own, so raw output
+    /// is run again without colors in the background; the copy or save
+    /// happens when it finishes (see `poll_plain_result`). Refused while the
+    /// query is waiting or running, as the shown result isn't its own yet.
+    fn request_plain_result(&mut self, then: PlainResultUse) {
+        if self.debouncer.is_pending() || self.is_query_running() {
+            self.status_message = Some("The query is still running; try again once its results are shown".to_string());
+            return;
         }
+
+        let output = self.jq_options.output;
+        let result = match &self.query_result {
+            Err(_) => Err("No results: the query failed".to_string()),
+            // Converted output has no colors
+            Ok(result) if output.format != OutputFormat::Json => Ok(result.clone()),
+            // Control characters in JSON strings are escaped, so every escape
+            // sequence is a color
+            Ok(result) if !output.raw => Ok(ansi::strip(result).into_owned()),
+            Ok(_) => {
+                let options = JqOptions {
+                    output: OutputOptions { monochrome: true, ..output },
+                    ..self.jq_options.clone()
+                };
+                let executor = JqExecutor::new(self.source().json_input.clone())
+                    .with_engine(self.engine.clone())
+                    .with_options(options)
+                    .with_limits(self.execution_limits);
+                let mut worker = QueryWorker::new(executor);
+                worker.submit(self.query());
+
+                self.plain_run = Some((worker, then));
+                self.status_message = Some("Running the query without colors…".to_string());
+                return;
+            }
+        };
+        self.use_plain_result(then, result);
+    }
+
+    /// Finish a copy or save waiting for the uncolored results, if they're ready
+    pub fn poll_plain_result(&mut self) {
+        let Some(result) = self.plain_run.as_mut().and_then(|(worker, _)| worker.try_recv()) else {
+            return;
+        };
+        if let Some((_, then)) = self.plain_run.take() {
+            self.use_plain_result(then, result.map_err(|e| format!("No results: {}", e)));
+        }
+    }
+
+    fn use_plain_result(&mut self, then: PlainResultUse, result: Result<String, String>) {
+        match (then, result) {
+            (PlainResultUse::Copy(target), result) => self.copy_result(target, result),
+            (PlainResultUse::Save(command), Ok(content)) => self.save_content(command, content),
+            (PlainResultUse::Save(_), Err(e)) => self.status_message = Some(e),
+        }
+    }
+
+    /// Switch to the next input (wraps around)
+    pub fn next_source(&mut self) {
+        self.select_source((self.active_source + 1) % self.sources.len());
+    }
+
+    /// Switch to the previous input (wraps around)
+    pub fn previous_source(&mut self) {
+        let count = self.sources.len();
+        self.select_source((self.active_source + count - 1) % count);
     }
 
     /// Check if the application should quit
@@ -129,7 +524,13 @@ impl App {
         }
 
         // Get suggestions based on context
-        let suggestions = get_suggestions(query, cursor_pos, &self.json_analyzer);
+        let suggestions = get_suggestions(
+            query,
+            cursor_pos,
+            &self.json_analyzer,
+            &self.variables,
+            self.engine.dialect(),
+        );
 
         // Update autocomplete state
         self.autocomplete.update_suggestions(suggestions);
@@ -171,7 +572,7 @@ impl App {
 
         // Hide autocomplete and execute query
         self.autocomplete.hide();
-        self.execute_query_and_update();
+        self.execute_query();
     }
 
     /// Move cursor to a specific column position (helper method)
@@ -197,15 +598,78 @@ impl App {
         }
     }
 
-    /// Execute query and update results (helper method)
-    fn execute_query_and_update(&mut self) {
-        let query = self.query();
-        self.query_result = self.executor.execute(query);
-        if let Ok(result) = &self.query_result {
-            self.last_successful_result = Some(result.clone());
-        }
+    /// Execute the current query once typing has been idle for the debounce delay
+    ///
+    /// The query still running for the previous text is cancelled right away.
+    pub fn schedule_query(&mut self) {
+        self.query_worker.cancel();
+        self.debouncer.schedule();
+
+        // Reset scroll when query changes
         self.results_scroll = 0;
     }
+
+    /// Start executing the current query in the background right away
+    ///
+    /// Any query still running is cancelled; its result will never be shown.
+    pub fn execute_query(&mut self) {
+        self.debouncer.cancel();
+
+        let query = self.query().to_string();
+        self.query_worker.submit(&query);
+
+        // Reset scroll when query changes
+        self.results_scroll = 0;
+    }
+
+    /// Advance timers: reload changed inputs, run a debounced query that is
+    /// due and collect finished results
+    pub fn tick(&mut self) {
+        self.poll_input_watcher();
+        self.poll_input_stream();
+
+        if self.debouncer.take_due() {
+            self.execute_query();
+        }
+        self.poll_query_result();
+        self.poll_plain_result();
+    }
+
+    /// Apply the result of a finished background query, if there is one
+    ///
+    /// Returns true if the results changed.
+    pub fn poll_query_result(&mut self) -> bool {
+        match self.query_worker.try_recv() {
+            Some(result) => {
+                let result = result.map_err(|e| e.to_string());
+
+                // Cache successful results
+                if let Ok(output) = &result {
+                    self.last_successful_result = Some(output.clone());
+                }
+                self.query_result = result;
+
+                if self.auto_scroll {
+                    self.results_scroll = self.max_scroll();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if a query is running in the background (not just waiting on the debounce delay)
+    pub fn is_query_running(&self) -> bool {
+        self.query_worker.is_running()
+    }
+}
+
+/// Analyze what the filter sees as its input for field autocomplete
+/// (every record of an NDJSON stream, an array of them with --slurp)
+fn analyze_input(json_input: &InputData, jq_options: &JqOptions) -> JsonAnalyzer {
+    let mut json_analyzer = JsonAnalyzer::new();
+    json_analyzer.analyze_documents(jq_options.input_mode.filter_inputs(json_input));
+    json_analyzer
 }
 
 /// Find the start position of the current token
@@ -256,7 +720,7 @@ mod tests {
     #[test]
     fn test_app_initialization() {
         let json = r#"{"name": "Alice", "age": 30}"#;
-        let app = App::new(json.to_string());
+        let app = App::new(vec![json.to_string().into()], &Config::default());
 
         // Check default state
         assert_eq!(app.focus, Focus::InputField);
@@ -269,7 +733,7 @@ mod tests {
     #[test]
     fn test_initial_query_result() {
         let json = r#"{"name": "Bob"}"#;
-        let app = App::new(json.to_string());
+        let app = App::new(vec![json.to_string().into()], &Config::default());
 
         // Initial query should execute identity filter "."
         assert!(app.query_result.is_ok());
@@ -277,6 +741,41 @@ mod tests {
         assert!(result.contains("Bob"));
     }
 
+    #[test]
+    fn test_copied_results_lose_only_colors() {
+        let json = r#"{"msg": "\u001b[31mred\u001b[0m"}"#;
+        let mut app = App::new(vec![json.to_string().into()], &Config::default());
+        app.copy(CopyTarget::Results);
+        assert_eq!(app.clipboard_output, Some(clipboard::osc52("{\n  \"msg\": \"\\u001b[31mred\\u001b[0m\"\n}")));
+
+        // Raw strings keep the escape sequences that are part of the data;
+        // the query runs again without colors in the background
+        app.textarea.insert_str(".msg");
+        app.set_output_options(OutputOptions { raw: true, ..Default::default() });
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !app.poll_query_result() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        app.clipboard_output = None;
+        app.copy(CopyTarget::Results);
+        assert!(app.plain_run.is_some());
+        assert_eq!(app.clipboard_output, None);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while app.plain_run.is_some() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            app.poll_plain_result();
+        }
+        assert_eq!(app.clipboard_output, Some(clipboard::osc52("\x1b[31mred\x1b[0m")));
+        assert_eq!(app.status_message.as_deref(), Some("Copied results (12 bytes)"));
+
+        // The event loop sends it to the terminal without waiting for a key
+        let mut terminal = Vec::new();
+        app.flush_clipboard(&mut terminal).unwrap();
+        assert_eq!(terminal, clipboard::osc52("\x1b[31mred\x1b[0m").into_bytes());
+        assert_eq!(app.clipboard_output, None);
+    }
+
     #[test]
     fn test_focus_enum() {
         assert_eq!(Focus::InputField, Focus::InputField);
@@ -294,7 +793,7 @@ mod tests {
     #[test]
     fn test_should_quit_getter() {
         let json = r#"{}"#;
-        let mut app = App::new(json.to_string());
+        let mut app = App::new(vec![json.to_string().into()], &Config::default());
 
         assert!(!app.should_quit());
 
@@ -305,7 +804,7 @@ mod tests {
     #[test]
     fn test_output_mode_getter() {
         let json = r#"{}"#;
-        let mut app = App::new(json.to_string());
+        let mut app = App::new(vec![json.to_string().into()], &Config::default());
 
         assert_eq!(app.output_mode(), None);
 
@@ -319,7 +818,7 @@ mod tests {
     #[test]
     fn test_query_getter_empty() {
         let json = r#"{"test": true}"#;
-        let app = App::new(json.to_string());
+        let app = App::new(vec![json.to_string().into()], &Config::default());
 
         assert_eq!(app.query(), "");
     }
@@ -327,7 +826,7 @@ mod tests {
     #[test]
     fn test_app_with_empty_json_object() {
         let json = "{}";
-        let app = App::new(json.to_string());
+        let app = App::new(vec![json.to_string().into()], &Config::default());
 
         assert!(app.query_result.is_ok());
     }
@@ -335,7 +834,7 @@ mod tests {
     #[test]
     fn test_app_with_json_array() {
         let json = r#"[1, 2, 3]"#;
-        let app = App::new(json.to_string());
+        let app = App::new(vec![json.to_string().into()], &Config::default());
 
         assert!(app.query_result.is_ok());
         let result = app.query_result.as_ref().unwrap();
@@ -343,4 +842,291 @@ mod tests {
         assert!(result.contains("2"));
         assert!(result.contains("3"));
     }
+
+    #[test]
+    fn test_execute_query_runs_in_background() {
+        let json = r#"{"name": "Alice", "city": "Seattle"}"#;
+        let mut app = App::new(vec![json.to_string().into()], &Config::default());
+        app.textarea.insert_str(".city");
+
+        app.execute_query();
+        assert!(app.is_query_running());
+
+        // Wait for the worker to finish
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !app.poll_query_result() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        assert!(!app.is_query_running());
+        let result = app.query_result.as_ref().unwrap();
+        assert!(result.contains("Seattle"));
+        assert!(!result.contains("Alice"));
+        assert_eq!(app.last_successful_result.as_deref(), Some(result.as_str()));
+    }
+
+    #[test]
+    fn test_schedule_query_waits_for_debounce() {
+        let config = Config {
+            debounce_ms: 60_000,
+            ..Config::default()
+        };
+        let mut app = App::new(vec![r#"{"a": 1}"#.to_string().into()], &config);
+        app.textarea.insert_str(".a");
+
+        app.schedule_query();
+        app.tick();
+
+        // Still waiting on the debounce delay, nothing spawned yet
+        assert!(app.debouncer.is_pending());
+        assert!(!app.query_worker.is_running());
+        assert!(!app.is_query_running());
+    }
+
+    #[test]
+    fn test_keystroke_cancels_running_query() {
+        let config = Config {
+            debounce_ms: 60_000,
+            ..Config::default()
+        };
+        let mut app = App::new(vec![r#"{"a": 1}"#.to_string().into()], &config);
+        app.textarea.insert_str(".a");
+        app.execute_query();
+        assert!(app.is_query_running());
+
+        app.textarea.insert_str("b");
+        app.schedule_query();
+
+        assert!(!app.is_query_running());
+        assert!(app.debouncer.is_pending());
+    }
+
+    #[test]
+    fn test_copy_and_save_wait_for_the_current_query() {
+        let config = Config {
+            debounce_ms: 60_000,
+            ..Config::default()
+        };
+        let mut app = App::new(vec![r#"{"a": 1}"#.to_string().into()], &config);
+        app.textarea.insert_str(".a");
+        app.schedule_query();
+
+        // The shown results are still those of `.`
+        app.copy(CopyTarget::Results);
+        assert_eq!(app.clipboard_output, None);
+        assert!(app.plain_run.is_none());
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("The query is still running; try again once its results are shown")
+        );
+
+        app.set_output_options(OutputOptions { raw: true, ..Default::default() });
+        app.copy(CopyTarget::Results);
+        assert!(app.plain_run.is_none());
+    }
+
+    #[test]
+    fn test_tick_runs_due_query() {
+        let config = Config {
+            debounce_ms: 0,
+            ..Config::default()
+        };
+        let mut app = App::new(vec![r#"{"a": 1}"#.to_string().into()], &config);
+        app.textarea.insert_str(".a");
+
+        app.schedule_query();
+        app.tick();
+
+        assert!(!app.debouncer.is_pending());
+        assert!(app.query_worker.is_running());
+    }
+
+    #[test]
+    fn test_execute_query_skips_debounce() {
+        let config = Config {
+            debounce_ms: 60_000,
+            ..Config::default()
+        };
+        let mut app = App::new(vec![r#"{"a": 1}"#.to_string().into()], &config);
+        app.textarea.insert_str(".a");
+
+        app.schedule_query();
+        app.execute_query();
+
+        assert!(!app.debouncer.is_pending());
+        assert!(app.query_worker.is_running());
+    }
+
+    #[test]
+    fn test_slurp_mode_analyzes_array_of_documents() {
+        let mut config = Config::default();
+        config.jq_options.input_mode.slurp = true;
+        let app = App::new(vec!["{\"id\": 1}\n{\"id\": 2}".to_string().into()], &config);
+
+        // The filter sees an array, so fields are found under .[]
+        let suggestions = app.json_analyzer.get_contextual_field_suggestions(".[]", "");
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].text, ".id");
+
+        let result = app.query_result.as_ref().unwrap();
+        assert!(result.contains('1') && result.contains('2'));
+    }
+
+    #[test]
+    fn test_csv_columns_are_suggested_as_fields() {
+        use crate::input::delimited::{CsvOptions, delimited_to_json};
+
+        let json = delimited_to_json("name,city\nAlice,Seattle\n", b',', &CsvOptions::default()).unwrap();
+        let app = App::new(vec![json.into()], &Config::default());
+
+        let suggestions = app.json_analyzer.get_contextual_field_suggestions(".[]", "");
+        let fields: Vec<_> = suggestions.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(fields, vec![".city", ".name"]);
+    }
+
+    #[test]
+    fn test_select_source_reruns_query_against_new_input() {
+        let sources = vec![
+            InputSource::new("staging.json", r#"{"env": "staging", "replicas": 1}"#.to_string()),
+            InputSource::new("prod.json", r#"{"env": "prod", "region": "eu"}"#.to_string()),
+        ];
+        let mut app = App::new(sources, &Config::default());
+        app.textarea.insert_str(".env");
+
+        app.next_source();
+        assert_eq!(app.active_source, 1);
+        assert_eq!(app.source().name.as_deref(), Some("prod.json"));
+        assert!(app.last_successful_result.is_none());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !app.poll_query_result() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(app.query_result.as_ref().unwrap().contains("prod"));
+
+        // Autocomplete follows the selected file
+        let fields: Vec<_> = app
+            .json_analyzer
+            .get_contextual_field_suggestions("", "")
+            .into_iter()
+            .map(|s| s.text)
+            .collect();
+        assert!(fields.contains(&".region".to_string()));
+        assert!(!fields.contains(&".replicas".to_string()));
+    }
+
+    #[test]
+    fn test_source_switching_wraps_around() {
+        let sources = vec![
+            InputSource::new("a.json", "1".to_string()),
+            InputSource::new("b.json", "2".to_string()),
+            InputSource::new("c.json", "3".to_string()),
+        ];
+        let mut app = App::new(sources, &Config::default());
+
+        app.previous_source();
+        assert_eq!(app.active_source, 2);
+        app.next_source();
+        assert_eq!(app.active_source, 0);
+    }
+
+    #[test]
+    fn test_reload_keeps_query_scroll_and_mode() {
+        let mut app = App::new(vec![r#"{"status": "ok"}"#.to_string().into()], &Config::default());
+        app.textarea.insert_str(".status");
+        app.editor_mode = EditorMode::Normal;
+        app.results_scroll = 3;
+
+        app.reload_sources(vec![r#"{"status": "degraded", "since": 5}"#.to_string().into()]);
+
+        assert_eq!(app.query(), ".status");
+        assert_eq!(app.textarea.cursor(), (0, 7));
+        assert_eq!(app.editor_mode, EditorMode::Normal);
+        assert_eq!(app.results_scroll, 3);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !app.poll_query_result() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(app.query_result.as_ref().unwrap().contains("degraded"));
+        let fields = app.json_analyzer.get_contextual_field_suggestions("", "");
+        assert!(fields.iter().any(|s| s.text == ".since"));
+    }
+
+    #[test]
+    fn test_watched_file_with_invalid_content_keeps_last_good_data() {
+        use crate::input::format::InputOptions;
+        use crate::input::mode::InputMode;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        std::fs::write(&path, r#"{"status": "ok"}"#).unwrap();
+
+        let watcher = InputWatcher::new(vec![path.clone()], InputOptions::default(), InputMode::default(), false)
+            .with_interval(Duration::ZERO);
+        let mut app = App::new(vec![r#"{"status": "ok"}"#.to_string().into()], &Config::default())
+            .with_input_watcher(watcher);
+
+        // Re-read in the background
+        let tick_until = |app: &mut App, done: fn(&App) -> bool| {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while !done(app) && std::time::Instant::now() < deadline {
+                app.tick();
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        };
+
+        std::fs::write(&path, r#"{"status": "#).unwrap();
+        tick_until(&mut app, |app| app.input_error.is_some());
+
+        assert!(app.input_error.as_deref().unwrap().contains("Invalid JSON"));
+        assert_eq!(app.source().json_input, r#"{"status": "ok"}"#);
+        assert!(app.query_result.as_ref().unwrap().contains("ok"));
+
+        // Fixing the file clears the banner
+        std::fs::write(&path, r#"{"status": "fixed"}"#).unwrap();
+        tick_until(&mut app, |app| app.input_error.is_none());
+
+        assert!(app.input_error.is_none());
+        assert!(app.source().json_input.text().contains("fixed"));
+    }
+
+    #[test]
+    fn test_streamed_records_are_queried_as_they_arrive() {
+        let input = std::io::Cursor::new("{\"level\": \"info\"}\n{\"level\": \"error\", \"code\": 7}\n");
+        let stream = InputStream::spawn(input, false, Some(10));
+        let mut app = App::new(vec![InputSource::new("stdin", String::new())], &Config::default())
+            .with_input_stream(stream);
+        app.textarea.insert_str(".level");
+        assert!(app.auto_scroll);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::time::Instant::now() < deadline {
+            app.tick();
+            if app.input_stream.as_ref().unwrap().record_count() == 2
+                && !app.is_query_running()
+                && app.query_result.as_ref().is_ok_and(|r| r.contains("error"))
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let result = app.query_result.as_ref().unwrap();
+        assert!(result.contains("info") && result.contains("error"));
+        assert!(app.json_analyzer.get_contextual_field_suggestions("", "").iter().any(|s| s.text == ".code"));
+    }
+
+    #[test]
+    fn test_auto_scroll_follows_new_results() {
+        let mut app = App::new(vec!["[1, 2, 3, 4, 5, 6, 7, 8]".to_string().into()], &Config::default());
+        app.results_viewport_height = 3;
+
+        app.toggle_auto_scroll();
+        assert!(app.auto_scroll);
+        assert_eq!(app.results_scroll, app.max_scroll());
+
+        app.toggle_auto_scroll();
+        assert!(!app.auto_scroll);
+    }
 }
@@ -1,14 +1,18 @@
 use super::jq_functions::filter_builtins;
 use super::json_analyzer::JsonAnalyzer;
-use super::state::Suggestion;
+use super::state::{Suggestion, SuggestionType};
+use crate::query::engine::Dialect;
 
 /// Context information about what's being typed
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum SuggestionContext {
     /// At start or after pipe/operator - suggest functions and patterns
     FunctionContext,
     /// After a dot - suggest field names
     FieldContext,
+    /// After a `$` - suggest variable names
+    VariableContext,
 }
 
 /// Analyze query text and cursor position to determine what to suggest
@@ -16,6 +20,8 @@ pub fn get_suggestions(
     query: &str,
     cursor_pos: usize,
     json_analyzer: &JsonAnalyzer,
+    variables: &[String],
+    dialect: Dialect,
 ) -> Vec<Suggestion> {
     // Get the text before cursor
     let before_cursor = &query[..cursor_pos.min(query.len())];
@@ -36,12 +42,28 @@ pub fn get_suggestions(
             if partial.is_empty() {
                 Vec::new()
             } else {
-                filter_builtins(&partial)
+                filter_builtins(&partial, dialect)
             }
         }
+        SuggestionContext::VariableContext => {
+            // Defined variables first, then built-in ones like $ENV
+            let mut suggestions = filter_variables(variables, &partial);
+            suggestions.extend(filter_builtins(&partial, dialect));
+            suggestions
+        }
     }
 }
 
+/// Filter variable names (with leading `$`) by prefix
+fn filter_variables(variables: &[String], prefix: &str) -> Vec<Suggestion> {
+    let prefix_lower = prefix.to_lowercase();
+    variables
+        .iter()
+        .filter(|name| name.to_lowercase().starts_with(&prefix_lower))
+        .map(|name| Suggestion::new(name.clone(), SuggestionType::Variable))
+        .collect()
+}
+
 /// Extract the jq path before the current field being typed
 /// Examples:
 ///   ".products.ty" -> ".products"
@@ -269,6 +291,105 @@ fn find_last_pipe_outside_parens(text: &str) -> Option<usize> {
     None
 }
 
+/// What the scanner in `find_last_top_level_pipe` is inside of
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Nesting {
+    /// `( )`, `[ ]` or `{ }`
+    Group,
+    /// `" "`
+    String,
+    /// `\( )` inside a string
+    Interpolation,
+    /// `if ... end`
+    If,
+}
+
+/// Find the last pipe '|' that splits a complete query into two stages,
+/// `prefix | tail`, where the tail only sees the prefix's output
+/// Returns the BYTE position of the pipe, or None if the query is a single stage
+///
+/// Unlike `find_last_pipe_outside_parens` this expects a whole query: pipes
+/// inside brackets, braces, strings (including `\(...)` interpolation) and
+/// `if ... end` don't count, and neither does `|=`. Everything after a
+/// top-level `as $x` or `label $name` binding stays in one stage, as it may
+/// use the variable, and queries defining functions or importing modules are
+/// a single stage.
+///
+/// Examples:
+///   ".items[] | select(.x | .y) | .name" -> Some(27) (pipe before .name)
+///   ".a | \"\\(.b | .c)\"" -> Some(3) (the pipe in the string doesn't count)
+///   ".[] | . as $x | $x" -> Some(4) (not the pipe after the binding)
+///   ".a |= . + 1" -> None (update-assignment, not a pipe)
+pub fn find_last_top_level_pipe(query: &str) -> Option<usize> {
+    let bytes = query.as_bytes();
+    let mut stack: Vec<Nesting> = Vec::new();
+    let mut last_pipe = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+
+        if stack.last() == Some(&Nesting::String) {
+            match byte {
+                b'\\' if bytes.get(i + 1) == Some(&b'(') => {
+                    stack.push(Nesting::Interpolation);
+                    i += 1;
+                }
+                // Skip the escaped character
+                b'\\' => i += 1,
+                b'"' => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+
+        match byte {
+            b'"' => stack.push(Nesting::String),
+            b'(' | b'[' | b'{' => stack.push(Nesting::Group),
+            b')' | b']' | b'}' => {
+                if matches!(stack.last(), Some(Nesting::Group | Nesting::Interpolation)) {
+                    stack.pop();
+                }
+            }
+            // Comment to the end of the line
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'|' if stack.is_empty() && bytes.get(i + 1) != Some(&b'=') => last_pipe = Some(i),
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+
+                // `.end`, `$if` and `@text` aren't keywords
+                let is_keyword = start == 0 || !matches!(bytes[start - 1], b'.' | b'$' | b'@');
+                if is_keyword {
+                    match &query[start..i] {
+                        "if" => stack.push(Nesting::If),
+                        "end" if stack.last() == Some(&Nesting::If) => {
+                            stack.pop();
+                        }
+                        "def" | "import" | "include" if stack.is_empty() => return None,
+                        "as" | "label" if stack.is_empty() => break,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    last_pipe
+}
+
 /// Find the position of an unmatched opening parenthesis '('
 /// Returns the BYTE position of the innermost (rightmost) unmatched '(', or None if all are matched
 /// This is used to detect if we're inside a function call
@@ -402,6 +523,11 @@ fn analyze_context(before_cursor: &str) -> (SuggestionContext, String) {
     // Extract the partial word
     let partial: String = chars[start..i].iter().collect();
 
+    // Check if the partial is a variable reference (but not $var.field)
+    if partial.starts_with('$') && !partial.contains('.') {
+        return (SuggestionContext::VariableContext, partial);
+    }
+
     // Check if the partial starts with a dot (field access)
     if let Some(stripped) = partial.strip_prefix('.') {
         // Field context - return the part after the LAST dot (for nested fields like .user.na)
@@ -518,6 +644,48 @@ mod tests {
         assert_eq!(partial, "na");
     }
 
+    #[test]
+    fn test_variable_context() {
+        let (ctx, partial) = analyze_context("$en");
+        assert_eq!(ctx, SuggestionContext::VariableContext);
+        assert_eq!(partial, "$en");
+
+        let (ctx, partial) = analyze_context(".items[] | select(.env == $");
+        assert_eq!(ctx, SuggestionContext::VariableContext);
+        assert_eq!(partial, "$");
+    }
+
+    #[test]
+    fn test_variable_field_access_is_not_variable_context() {
+        let (ctx, _) = analyze_context("$ENV.PA");
+        assert_ne!(ctx, SuggestionContext::VariableContext);
+    }
+
+    #[test]
+    fn test_variable_suggestions() {
+        let analyzer = JsonAnalyzer::new();
+        let variables = vec!["$env_name".to_string(), "$limit".to_string()];
+
+        let suggestions = get_suggestions("$e", 2, &analyzer, &variables, Dialect::default());
+        let texts: Vec<&str> = suggestions.iter().map(|s| s.text.as_str()).collect();
+
+        // Defined variable first, then matching builtin
+        assert_eq!(texts, vec!["$env_name", "$ENV"]);
+        assert_eq!(suggestions[0].suggestion_type, SuggestionType::Variable);
+    }
+
+    #[test]
+    fn test_bare_dollar_suggests_all_variables() {
+        let analyzer = JsonAnalyzer::new();
+        let variables = vec!["$env_name".to_string(), "$limit".to_string()];
+
+        let suggestions = get_suggestions("$", 1, &analyzer, &variables, Dialect::default());
+        let texts: Vec<&str> = suggestions.iter().map(|s| s.text.as_str()).collect();
+
+        assert!(texts.contains(&"$env_name"));
+        assert!(texts.contains(&"$limit"));
+    }
+
     #[test]
     fn test_extract_path_root_level() {
         assert_eq!(extract_path_before_current_field("."), "");
@@ -824,6 +992,35 @@ mod tests {
         assert_eq!(find_last_colon_outside_brackets("func(.x[0:5])"), None);
     }
 
+    #[test]
+    fn test_find_last_top_level_pipe() {
+        assert_eq!(find_last_top_level_pipe(".items[] | select(.x | .y) | .name"), Some(27));
+        assert_eq!(find_last_top_level_pipe(".a|.b"), Some(2));
+        assert_eq!(find_last_top_level_pipe(".a"), None);
+        assert_eq!(find_last_top_level_pipe(".a |= . + 1"), None);
+        assert_eq!(find_last_top_level_pipe(".a | .b |= 1"), Some(3));
+    }
+
+    #[test]
+    fn test_find_last_top_level_pipe_skips_nested_pipes() {
+        assert_eq!(find_last_top_level_pipe("[.[] | .a]"), None);
+        assert_eq!(find_last_top_level_pipe("{a: (.b | .c)}"), None);
+        assert_eq!(find_last_top_level_pipe(r#".a | "x | \(.b | "|") | y""#), Some(3));
+        assert_eq!(find_last_top_level_pipe(r#"."a|b" | "\"|""#), Some(7));
+        assert_eq!(find_last_top_level_pipe("if .a then .b | .c else .d end | .e"), Some(31));
+        assert_eq!(find_last_top_level_pipe(".if | .end"), Some(4));
+        assert_eq!(find_last_top_level_pipe(".a | .b # | .c\n"), Some(3));
+    }
+
+    #[test]
+    fn test_find_last_top_level_pipe_keeps_bindings_in_one_stage() {
+        assert_eq!(find_last_top_level_pipe(".[] | . as $x | $x.a"), Some(4));
+        assert_eq!(find_last_top_level_pipe(".a | reduce .[] as $x (0; . + $x) | . * 2"), Some(3));
+        assert_eq!(find_last_top_level_pipe("label $out | .a | .b"), None);
+        assert_eq!(find_last_top_level_pipe("def f: . + 1; .a | f"), None);
+        assert_eq!(find_last_top_level_pipe("(def f: . + 1; .a | f) | .b"), Some(23));
+    }
+
     // Edge case tests
     #[test]
     fn test_edge_cases() {
@@ -1,6 +1,36 @@
 use super::state::{Suggestion, SuggestionType};
+use crate::query::engine::Dialect;
+use crate::query::version::JqVersion;
 use std::sync::LazyLock;
 
+/// jq release that introduced a builtin; builtins not listed exist in every jq
+const INTRODUCED_IN: &[(&str, JqVersion)] = &[
+    ("ltrimstr", JqVersion::new(1, 5, 0)),
+    ("rtrimstr", JqVersion::new(1, 5, 0)),
+    ("splits", JqVersion::new(1, 5, 0)),
+    ("@base64d", JqVersion::new(1, 6, 0)),
+    ("$ENV", JqVersion::new(1, 6, 0)),
+    ("pick", JqVersion::new(1, 7, 0)),
+    ("have_literal_numbers", JqVersion::new(1, 7, 0)),
+    ("have_decnum", JqVersion::new(1, 7, 0)),
+    ("abs", JqVersion::new(1, 7, 1)),
+    ("toarray", JqVersion::new(1, 7, 1)),
+    ("trim", JqVersion::new(1, 7, 1)),
+    ("ltrim", JqVersion::new(1, 7, 1)),
+    ("rtrim", JqVersion::new(1, 7, 1)),
+];
+
+/// jq builtins the embedded jaq engine doesn't implement
+const MISSING_IN_JAQ: &[&str] = &[
+    "setpath",
+    "delpaths",
+    "leaf_paths",
+    "pick",
+    "toarray",
+    "have_literal_numbers",
+    "have_decnum",
+];
+
 /// Static list of all jq built-in functions, operators, and patterns
 /// Built once at first access and reused for performance
 static JQ_BUILTINS: LazyLock<Vec<Suggestion>> = LazyLock::new(|| {
@@ -100,6 +130,8 @@ static JQ_BUILTINS: LazyLock<Vec<Suggestion>> = LazyLock::new(|| {
             .with_description("Get all paths (leaf paths)"),
         Suggestion::new("leaf_paths", SuggestionType::Function)
             .with_description("Get all leaf paths"),
+        Suggestion::new("pick", SuggestionType::Function)
+            .with_description("Keep only the given paths"),
     ]);
 
     // String functions
@@ -116,6 +148,12 @@ static JQ_BUILTINS: LazyLock<Vec<Suggestion>> = LazyLock::new(|| {
             .with_description("Remove prefix string"),
         Suggestion::new("rtrimstr", SuggestionType::Function)
             .with_description("Remove suffix string"),
+        Suggestion::new("trim", SuggestionType::Function)
+            .with_description("Remove surrounding whitespace"),
+        Suggestion::new("ltrim", SuggestionType::Function)
+            .with_description("Remove leading whitespace"),
+        Suggestion::new("rtrim", SuggestionType::Function)
+            .with_description("Remove trailing whitespace"),
         Suggestion::new("ascii_downcase", SuggestionType::Function)
             .with_description("Convert to lowercase"),
         Suggestion::new("ascii_upcase", SuggestionType::Function)
@@ -150,6 +188,8 @@ static JQ_BUILTINS: LazyLock<Vec<Suggestion>> = LazyLock::new(|| {
             .with_description("Select non-null values"),
         Suggestion::new("scalars", SuggestionType::Function)
             .with_description("Select non-iterable values"),
+        Suggestion::new("toarray", SuggestionType::Function)
+            .with_description("Wrap non-arrays in an array"),
     ]);
 
     // Math functions
@@ -209,6 +249,10 @@ static JQ_BUILTINS: LazyLock<Vec<Suggestion>> = LazyLock::new(|| {
             .with_description("Environment object"),
         Suggestion::new("error", SuggestionType::Function).with_description("Raise error"),
         Suggestion::new("empty", SuggestionType::Function).with_description("Produce no output"),
+        Suggestion::new("have_literal_numbers", SuggestionType::Function)
+            .with_description("Whether number literals keep their precision"),
+        Suggestion::new("have_decnum", SuggestionType::Function)
+            .with_description("Whether jq was built with decimal numbers"),
     ]);
 
     // Conditional/logic
@@ -242,8 +286,9 @@ static JQ_BUILTINS: LazyLock<Vec<Suggestion>> = LazyLock::new(|| {
     builtins
 });
 
-/// Filter jq builtins by prefix (optimized for performance)
-pub fn filter_builtins(prefix: &str) -> Vec<Suggestion> {
+/// Filter jq builtins by prefix (optimized for performance), leaving out
+/// those the query engine doesn't provide
+pub fn filter_builtins(prefix: &str, dialect: Dialect) -> Vec<Suggestion> {
     if prefix.is_empty() {
         return Vec::new();
     }
@@ -252,6 +297,65 @@ pub fn filter_builtins(prefix: &str) -> Vec<Suggestion> {
     JQ_BUILTINS
         .iter()
         .filter(|s| s.text.to_lowercase().starts_with(&prefix_lower))
+        .filter(|s| is_available(&s.text, dialect))
         .cloned()
         .collect()
 }
+
+/// Whether `builtin` exists in `dialect`; with an unknown jq version everything is offered
+fn is_available(builtin: &str, dialect: Dialect) -> bool {
+    match dialect {
+        Dialect::Jq(None) => true,
+        Dialect::Jq(Some(version)) => INTRODUCED_IN
+            .iter()
+            .find(|(name, _)| *name == builtin)
+            .is_none_or(|(_, introduced)| version >= *introduced),
+        Dialect::Jaq => !MISSING_IN_JAQ.contains(&builtin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(suggestions: Vec<Suggestion>) -> Vec<String> {
+        suggestions.into_iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn test_gated_builtins_are_listed() {
+        for name in INTRODUCED_IN.iter().map(|(name, _)| *name).chain(MISSING_IN_JAQ.iter().copied()) {
+            assert!(JQ_BUILTINS.iter().any(|s| s.text == name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_newer_builtins_hidden_for_older_jq() {
+        let jq16 = Dialect::Jq(Some(JqVersion::new(1, 6, 0)));
+        assert!(texts(filter_builtins("pi", jq16)).is_empty());
+        assert!(!texts(filter_builtins("ab", jq16)).contains(&"abs".to_string()));
+        assert_eq!(texts(filter_builtins("ltrimstr", jq16)), vec!["ltrimstr"]);
+    }
+
+    #[test]
+    fn test_newer_builtins_offered_for_newer_jq() {
+        let jq17 = Dialect::Jq(Some(JqVersion::new(1, 7, 0)));
+        assert_eq!(texts(filter_builtins("pi", jq17)), vec!["pick"]);
+        assert!(!texts(filter_builtins("toa", jq17)).contains(&"toarray".to_string()));
+
+        let jq171 = Dialect::Jq(Some(JqVersion::new(1, 7, 1)));
+        assert_eq!(texts(filter_builtins("toa", jq171)), vec!["toarray"]);
+        assert!(texts(filter_builtins("ab", jq171)).contains(&"abs".to_string()));
+    }
+
+    #[test]
+    fn test_unknown_version_offers_everything() {
+        assert_eq!(texts(filter_builtins("pi", Dialect::Jq(None))), vec!["pick"]);
+    }
+
+    #[test]
+    fn test_jaq_hides_missing_builtins() {
+        assert!(texts(filter_builtins("pi", Dialect::Jaq)).is_empty());
+        assert!(texts(filter_builtins("ab", Dialect::Jaq)).contains(&"abs".to_string()));
+    }
+}
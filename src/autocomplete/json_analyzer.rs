@@ -18,16 +18,32 @@ impl JsonAnalyzer {
         }
     }
 
-    /// Analyze JSON value and extract all field names
+    /// Parse JSON text and extract all field names (used in tests)
+    #[cfg(test)]
     pub fn analyze(&mut self, json: &str) -> Result<(), String> {
-        self.field_names.clear();
-
         let value: Value = serde_json::from_str(json)
             .map_err(|e| format!("Failed to parse JSON: {}", e))?;
 
+        self.analyze_value(value);
+        Ok(())
+    }
+
+    /// Analyze JSON value and extract all field names
+    pub fn analyze_value(&mut self, value: Value) {
+        self.field_names.clear();
         self.extract_fields(&value);
         self.root_value = Some(value);
-        Ok(())
+    }
+
+    /// Analyze every value the filter runs against (e.g. each NDJSON record),
+    /// merging their structure so fields from all documents are suggested
+    pub fn analyze_documents(&mut self, documents: impl IntoIterator<Item = Value>) {
+        let mut documents = documents.into_iter();
+        let mut merged = documents.next().unwrap_or(Value::Null);
+        for document in documents {
+            merge_schema(&mut merged, document);
+        }
+        self.analyze_value(merged);
     }
 
     /// Recursively extract field names from JSON value
@@ -190,6 +206,43 @@ fn detect_json_type(value: &Value) -> JsonFieldType {
     }
 }
 
+/// Merge the structure of `other` into `base`
+///
+/// Objects take the union of their fields, arrays merge their first elements
+/// (the element used for field discovery), and `null` is replaced by any
+/// concrete value. Otherwise the first type seen wins.
+fn merge_schema(base: &mut Value, other: Value) {
+    if base.is_null() {
+        *base = other;
+        return;
+    }
+
+    match (base, other) {
+        (Value::Object(base_map), Value::Object(other_map)) => {
+            for (key, value) in other_map {
+                match base_map.get_mut(&key) {
+                    Some(existing) => merge_schema(existing, value),
+                    None => {
+                        base_map.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base_arr), Value::Array(other_arr)) => {
+            let mut other_elements = other_arr.into_iter();
+            match base_arr.first_mut() {
+                Some(first) => {
+                    if let Some(other_first) = other_elements.next() {
+                        merge_schema(first, other_first);
+                    }
+                }
+                None => base_arr.extend(other_elements),
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Extract fields from a specific JSON value
 fn extract_fields_from_value(value: &Value, prefix: &str) -> Vec<Suggestion> {
     match value {
@@ -939,4 +992,43 @@ mod tests {
             "Array[Array[Number]]"
         );
     }
+
+    #[test]
+    fn test_documents_merge_top_level_fields() {
+        let mut analyzer = JsonAnalyzer::new();
+        analyzer.analyze_documents(vec![
+            serde_json::json!({"level": "info", "msg": "started"}),
+            serde_json::json!({"level": "warn", "latency_ms": 1840}),
+        ]);
+
+        let suggestions = analyzer.get_contextual_field_suggestions("", "");
+        let texts: Vec<_> = suggestions.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec![".latency_ms", ".level", ".msg"]);
+    }
+
+    #[test]
+    fn test_documents_merge_nested_fields() {
+        let mut analyzer = JsonAnalyzer::new();
+        analyzer.analyze_documents(vec![
+            serde_json::json!({"error": null, "items": []}),
+            serde_json::json!({"error": {"code": 504}, "items": [{"id": 1}]}),
+            serde_json::json!({"error": {"upstream": "billing"}, "items": [{"name": "x"}]}),
+        ]);
+
+        let error_fields = analyzer.get_contextual_field_suggestions(".error", "");
+        let texts: Vec<_> = error_fields.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec![".code", ".upstream"]);
+
+        let item_fields = analyzer.get_contextual_field_suggestions(".items[]", "");
+        let texts: Vec<_> = item_fields.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec![".id", ".name"]);
+    }
+
+    #[test]
+    fn test_no_documents_gives_no_suggestions() {
+        let mut analyzer = JsonAnalyzer::new();
+        analyzer.analyze_documents(Vec::new());
+
+        assert!(analyzer.get_contextual_field_suggestions("", "").is_empty());
+    }
 }
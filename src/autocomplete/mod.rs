@@ -3,7 +3,7 @@ mod jq_functions;
 pub mod json_analyzer;
 mod state;
 
-pub use context::get_suggestions;
+pub use context::{find_last_top_level_pipe, get_suggestions};
 // JsonFieldType is part of public API for Suggestion struct
 #[allow(unused_imports)]
 pub use state::{AutocompleteState, JsonFieldType, SuggestionType};
@@ -11,6 +11,8 @@ pub enum SuggestionType {
     Operator,
     /// Common filter pattern (e.g., .[], .[0])
     Pattern,
+    /// Variable defined via jq options (e.g., $env_name from --arg)
+    Variable,
 }
 
 impl fmt::Display for SuggestionType {
@@ -20,6 +22,7 @@ impl fmt::Display for SuggestionType {
             SuggestionType::Field => write!(f, "field"),
             SuggestionType::Operator => write!(f, "op"),
             SuggestionType::Pattern => write!(f, "pat"),
+            SuggestionType::Variable => write!(f, "var"),
         }
     }
 }
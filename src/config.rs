@@ -0,0 +1,267 @@
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::JiqError;
+use crate::query::engine::{Engine, QueryEngine};
+use crate::query::executor::ExecutionLimits;
+use crate::query::jaq::Jaq;
+use crate::query::jq_process::JqProcess;
+use crate::query::options::JqOptions;
+use crate::query::output::ColorChoice;
+use crate::query::record::RecordFormat;
+use crate::query::version::JqVersion;
+
+/// Environment variable that overrides the config file location
+const CONFIG_PATH_ENV: &str = "JIQ_CONFIG";
+
+/// Environment variable naming the jq binary (overrides `jq_path` in the file)
+const JQ_PATH_ENV: &str = "JIQ_JQ";
+
+/// Default idle time before a typed query is executed
+pub const DEFAULT_DEBOUNCE_MS: u64 = 100;
+
+/// Default wall-clock limit for an interactive query
+pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+/// Default cap on captured jq output for an interactive query
+pub const DEFAULT_MAX_OUTPUT_MB: u64 = 50;
+
+/// Default memory for cached query results
+pub const DEFAULT_CACHE_MB: u64 = 64;
+
+/// Default number of streamed records kept in memory with `--follow`
+pub const DEFAULT_MAX_RECORDS: u64 = 10_000;
+
+/// User configuration, loaded from `~/.config/jiq/config.toml`
+///
+/// Every field is optional in the file; CLI flags override file values.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Milliseconds of typing inactivity before the query is executed
+    pub debounce_ms: u64,
+    /// Milliseconds a query may run before jq is killed (0 = no limit)
+    pub timeout_ms: u64,
+    /// Megabytes of jq output captured before jq is killed (0 = no limit)
+    pub max_output_mb: u64,
+    /// Newest records kept when following a stream (0 = no limit)
+    pub max_records: u64,
+    /// Megabytes of recent query results kept for instant revisits (0 = no cache)
+    pub cache_mb: u64,
+    /// jq implementation that runs queries: `auto`, `jq` or `jaq`
+    pub engine: Engine,
+    /// jq binary to run, a name looked up in PATH or a path (default `jq`)
+    pub jq_path: Option<PathBuf>,
+    /// Command also given copied text on stdin, e.g. `pbcopy` or
+    /// `xclip -selection clipboard`, for terminals without OSC 52
+    pub clipboard_command: Option<String>,
+    /// Release of the jq binary, detected at startup (never read from the file)
+    #[serde(skip)]
+    pub jq_version: Option<JqVersion>,
+    /// jq options from the command line (never read from the file)
+    #[serde(skip)]
+    pub jq_options: JqOptions,
+    /// When results printed on exit are colored (`--color`, never read from the file)
+    #[serde(skip)]
+    pub color: ColorChoice,
+    /// Print a record of the query instead of the results (`--record`, never read from the file)
+    #[serde(skip)]
+    pub record: Option<RecordFormat>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            max_output_mb: DEFAULT_MAX_OUTPUT_MB,
+            max_records: DEFAULT_MAX_RECORDS,
+            cache_mb: DEFAULT_CACHE_MB,
+            engine: Engine::default(),
+            jq_path: None,
+            clipboard_command: None,
+            jq_version: None,
+            jq_options: JqOptions::default(),
+            color: ColorChoice::default(),
+            record: None,
+        }
+    }
+}
+
+impl Config {
+    /// Limits applied to queries executed while the TUI is running
+    pub fn execution_limits(&self) -> ExecutionLimits {
+        ExecutionLimits {
+            timeout: (self.timeout_ms > 0).then(|| Duration::from_millis(self.timeout_ms)),
+            max_output_bytes: (self.max_output_mb > 0)
+                .then(|| (self.max_output_mb as usize).saturating_mul(1024 * 1024)),
+        }
+    }
+
+    /// Bytes of query results cached per input
+    pub fn cache_budget(&self) -> usize {
+        (self.cache_mb as usize).saturating_mul(1024 * 1024)
+    }
+
+    /// Cap on records retained from a followed stream
+    pub fn record_limit(&self) -> Option<usize> {
+        (self.max_records > 0).then_some(self.max_records as usize)
+    }
+
+    /// The jq binary to run
+    pub fn jq_program(&self) -> &Path {
+        self.jq_path.as_deref().unwrap_or(Path::new("jq"))
+    }
+
+    /// The backend running queries, for the already resolved `engine`
+    pub fn query_engine(&self) -> Arc<dyn QueryEngine> {
+        match self.engine {
+            // jiq re-runs itself for each query, so the limits can kill it
+            Engine::Jaq => match env::current_exe() {
+                Ok(exe) => Arc::new(Jaq::default().with_worker(exe)),
+                Err(_) => Arc::new(Jaq::default()),
+            },
+            Engine::Jq | Engine::Auto => {
+                Arc::new(JqProcess::new(self.jq_program()).with_version(self.jq_version))
+            }
+        }
+    }
+
+    /// Load the config file, falling back to defaults if it doesn't exist
+    ///
+    /// `$JIQ_JQ` overrides the file's `jq_path`.
+    pub fn load() -> Result<Self, JiqError> {
+        let mut config = match config_path() {
+            Some(path) if path.exists() => Self::load_from(&path)?,
+            _ => Self::default(),
+        };
+        if let Some(path) = env::var_os(JQ_PATH_ENV).filter(|path| !path.is_empty()) {
+            config.jq_path = Some(PathBuf::from(path));
+        }
+        Ok(config)
+    }
+
+    /// Load config from a specific TOML file
+    pub fn load_from(path: &Path) -> Result<Self, JiqError> {
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents).map_err(|e| JiqError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse config from TOML text
+    fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+}
+
+/// Resolve the config file location
+///
+/// `$JIQ_CONFIG` wins, then `$XDG_CONFIG_HOME/jiq/config.toml`,
+/// then `~/.config/jiq/config.toml`.
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_PATH_ENV) {
+        return Some(PathBuf::from(path));
+    }
+
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("jiq").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+        assert_eq!(config.debounce_ms, DEFAULT_DEBOUNCE_MS);
+    }
+
+    #[test]
+    fn test_empty_file_uses_defaults() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_parse_debounce() {
+        let config = Config::parse("debounce_ms = 250").unwrap();
+        assert_eq!(config.debounce_ms, 250);
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let config = Config::parse("timeout_ms = 2000\nmax_output_mb = 5").unwrap();
+        let limits = config.execution_limits();
+
+        assert_eq!(limits.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(limits.max_output_bytes, Some(5 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_zero_limits_disable_guards() {
+        let config = Config::parse("timeout_ms = 0\nmax_output_mb = 0").unwrap();
+        assert_eq!(config.execution_limits(), ExecutionLimits::default());
+    }
+
+    #[test]
+    fn test_parse_jq_path() {
+        let config = Config::parse("jq_path = \"/opt/jq-1.7/bin/jq\"\nengine = \"jq\"").unwrap();
+        assert_eq!(config.jq_program(), Path::new("/opt/jq-1.7/bin/jq"));
+        assert_eq!(config.engine, Engine::Jq);
+        assert_eq!(Config::default().jq_program(), Path::new("jq"));
+    }
+
+    #[test]
+    fn test_parse_clipboard_command() {
+        let config = Config::parse("clipboard_command = \"xclip -selection clipboard\"").unwrap();
+        assert_eq!(config.clipboard_command.as_deref(), Some("xclip -selection clipboard"));
+        assert_eq!(Config::default().clipboard_command, None);
+    }
+
+    #[test]
+    fn test_parse_cache_size() {
+        assert_eq!(Config::default().cache_budget(), 64 * 1024 * 1024);
+        assert_eq!(Config::parse("cache_mb = 8").unwrap().cache_budget(), 8 * 1024 * 1024);
+        assert_eq!(Config::parse("cache_mb = 0").unwrap().cache_budget(), 0);
+    }
+
+    #[test]
+    fn test_parse_max_records() {
+        assert_eq!(Config::default().record_limit(), Some(DEFAULT_MAX_RECORDS as usize));
+        assert_eq!(Config::parse("max_records = 500").unwrap().record_limit(), Some(500));
+        assert_eq!(Config::parse("max_records = 0").unwrap().record_limit(), None);
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        assert!(Config::parse("debounce = 250").is_err());
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "debounce_ms = 0").unwrap();
+
+        let config = Config::load_from(file.path()).unwrap();
+        assert_eq!(config.debounce_ms, 0);
+    }
+
+    #[test]
+    fn test_load_from_invalid_file_returns_config_error() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "debounce_ms = \"fast\"").unwrap();
+
+        match Config::load_from(file.path()) {
+            Err(JiqError::Config(msg)) => assert!(msg.contains("debounce_ms")),
+            other => panic!("Expected Config error, got {:?}", other),
+        }
+    }
+}
@@ -1,14 +1,133 @@
+use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Custom error types for jiq
 #[derive(Debug, Error)]
 pub enum JiqError {
-    #[error("jq binary not found in PATH.\n\nInstall jq from: https://jqlang.org/download/")]
-    JqNotFound,
+    #[error("jq binary not found: {0}\n\nInstall jq from: https://jqlang.org/download/\nor use the built-in engine with --engine jaq")]
+    JqNotFound(String),
 
     #[error("Invalid JSON input: {0}")]
     InvalidJson(String),
 
+    #[error("Invalid YAML input: {0}")]
+    InvalidYaml(String),
+
+    #[error("Invalid TOML input: {0}")]
+    InvalidToml(String),
+
+    #[error("Invalid CSV input: {0}")]
+    InvalidCsv(String),
+
+    #[error("Failed to decompress {0} input: {1}")]
+    Decompress(String, String),
+
+    #[error("Invalid argument {0}")]
+    InvalidArgument(String),
+
+    #[error("Invalid config file {0}")]
+    Config(String),
+
+    /// An error in one of several input files
+    #[error("{}: {source}", path.display())]
+    File {
+        path: PathBuf,
+        source: Box<JiqError>,
+    },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
+
+/// Errors from executing a single jq query
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum QueryError {
+    /// jq rejected the query or failed at runtime (stderr output)
+    #[error("{0}")]
+    Jq(String),
+
+    #[error("Query timed out after {}", format_duration(*.0))]
+    TimedOut(Duration),
+
+    #[error("Output truncated at {} (query stopped)", format_bytes(*.0))]
+    OutputTooLarge(usize),
+
+    #[error("Query cancelled")]
+    Cancelled,
+
+    /// Spawning or talking to the jq process failed
+    #[error("{0}")]
+    Process(String),
+
+    /// The result can't be written in the chosen output format (e.g. CSV)
+    #[error("{0}")]
+    Output(String),
+}
+
+/// Format a duration compactly, e.g. "2s", "1.5s", "250ms"
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else if millis.is_multiple_of(1000) {
+        format!("{}s", millis / 1000)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+/// Format a byte count with a binary unit, e.g. "50 MB", "64 KB"
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["bytes", "KB", "MB", "GB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 || value.fract() == 0.0 {
+        format!("{} {}", value as u64, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timed_out_message() {
+        let error = QueryError::TimedOut(Duration::from_secs(2));
+        assert_eq!(error.to_string(), "Query timed out after 2s");
+    }
+
+    #[test]
+    fn test_output_too_large_message() {
+        let error = QueryError::OutputTooLarge(50 * 1024 * 1024);
+        assert_eq!(error.to_string(), "Output truncated at 50 MB (query stopped)");
+    }
+
+    #[test]
+    fn test_jq_error_message_passes_through() {
+        let error = QueryError::Jq("jq: error: syntax error".to_string());
+        assert_eq!(error.to_string(), "jq: error: syntax error");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.5s");
+        assert_eq!(format_duration(Duration::from_secs(10)), "10s");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 bytes");
+        assert_eq!(format_bytes(64 * 1024), "64 KB");
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MB");
+    }
+}
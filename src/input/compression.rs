@@ -0,0 +1,180 @@
+use std::fmt;
+use std::io::{self, Cursor, Read};
+
+/// Compression formats recognized by their magic bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+/// Longest magic number we look for (xz)
+const MAGIC_LEN: usize = 6;
+
+impl Compression {
+    /// Detect compression from the first bytes of a stream
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        match header {
+            [0x1f, 0x8b, ..] => Some(Compression::Gzip),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Compression::Zstd),
+            // "BZh" followed by the block size digit
+            [b'B', b'Z', b'h', b'1'..=b'9', ..] => Some(Compression::Bzip2),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// Whether a file extension names a compression format (e.g. "gz")
+    pub fn is_extension(extension: &str) -> bool {
+        matches!(extension, "gz" | "gzip" | "zst" | "zstd" | "bz2" | "xz")
+    }
+
+    /// Command line that writes the decompressed contents of the files after it
+    /// (or of stdin) to stdout
+    pub fn decompress_command(self) -> [&'static str; 2] {
+        match self {
+            Compression::Gzip => ["gzip", "-dc"],
+            Compression::Zstd => ["zstd", "-dc"],
+            Compression::Bzip2 => ["bzip2", "-dc"],
+            Compression::Xz => ["xz", "-dc"],
+        }
+    }
+
+    /// Wrap a reader so it yields the decompressed data
+    fn decoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            // Multi-member decoders handle concatenated archives (`cat a.gz b.gz`)
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Compression::Xz => Box::new(liblzma::read::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Gzip => write!(f, "gzip"),
+            Compression::Zstd => write!(f, "zstd"),
+            Compression::Bzip2 => write!(f, "bzip2"),
+            Compression::Xz => write!(f, "xz"),
+        }
+    }
+}
+
+/// Peek at the magic bytes of `reader` and decompress it on the fly if needed
+///
+/// Works on non-seekable streams like stdin: the peeked bytes are chained
+/// back in front of the rest of the stream.
+pub fn decompressing_reader<'a>(
+    mut reader: impl Read + 'a,
+) -> io::Result<(Option<Compression>, Box<dyn Read + 'a>)> {
+    let mut header = Vec::with_capacity(MAGIC_LEN);
+    reader.by_ref().take(MAGIC_LEN as u64).read_to_end(&mut header)?;
+
+    let compression = Compression::detect(&header);
+    let stream = Cursor::new(header).chain(reader);
+
+    let decompressed = match compression {
+        Some(compression) => compression.decoder(stream)?,
+        None => Box::new(stream),
+    };
+    Ok((compression, decompressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const JSON: &str = r#"{"name": "Alice", "tags": ["a", "b"]}"#;
+
+    fn read_all(data: Vec<u8>) -> (Option<Compression>, String) {
+        let (compression, mut reader) = decompressing_reader(Cursor::new(data)).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        (compression, text)
+    }
+
+    #[test]
+    fn test_plain_input_passes_through() {
+        assert_eq!(read_all(JSON.as_bytes().to_vec()), (None, JSON.to_string()));
+    }
+
+    #[test]
+    fn test_short_input_passes_through() {
+        assert_eq!(read_all(b"1".to_vec()), (None, "1".to_string()));
+        assert_eq!(read_all(Vec::new()), (None, String::new()));
+    }
+
+    #[test]
+    fn test_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(JSON.as_bytes()).unwrap();
+
+        assert_eq!(
+            read_all(encoder.finish().unwrap()),
+            (Some(Compression::Gzip), JSON.to_string())
+        );
+    }
+
+    #[test]
+    fn test_concatenated_gzip_members() {
+        let mut data = Vec::new();
+        for part in ["{\"a\": 1}\n", "{\"a\": 2}\n"] {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(part.as_bytes()).unwrap();
+            data.extend(encoder.finish().unwrap());
+        }
+
+        assert_eq!(read_all(data).1, "{\"a\": 1}\n{\"a\": 2}\n");
+    }
+
+    #[test]
+    fn test_zstd() {
+        let data = zstd::stream::encode_all(JSON.as_bytes(), 0).unwrap();
+        assert_eq!(read_all(data), (Some(Compression::Zstd), JSON.to_string()));
+    }
+
+    #[test]
+    fn test_bzip2() {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(JSON.as_bytes()).unwrap();
+
+        assert_eq!(
+            read_all(encoder.finish().unwrap()),
+            (Some(Compression::Bzip2), JSON.to_string())
+        );
+    }
+
+    #[test]
+    fn test_xz() {
+        let mut encoder = liblzma::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(JSON.as_bytes()).unwrap();
+
+        assert_eq!(
+            read_all(encoder.finish().unwrap()),
+            (Some(Compression::Xz), JSON.to_string())
+        );
+    }
+
+    #[test]
+    fn test_truncated_archive_is_an_error() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(JSON.as_bytes()).unwrap();
+        let mut data = encoder.finish().unwrap();
+        data.truncate(data.len() / 2);
+
+        let (_, mut reader) = decompressing_reader(Cursor::new(data)).unwrap();
+        let mut text = String::new();
+        assert!(reader.read_to_string(&mut text).is_err());
+    }
+
+    #[test]
+    fn test_text_starting_like_bzip2_is_not_detected() {
+        assert_eq!(Compression::detect(b"BZh is just text"), None);
+    }
+}
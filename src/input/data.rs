@@ -0,0 +1,202 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+/// Input text shared by the UI, the autocomplete analyzer and query threads
+///
+/// Cloning only bumps reference counts, so a multi-GB input exists once in
+/// memory however many executors hold it. Large files can be memory-mapped
+/// instead of read, and concatenated files keep their own buffers.
+#[derive(Clone, Default)]
+pub struct InputData {
+    chunks: Vec<Arc<Chunk>>,
+}
+
+/// One contiguous piece of the input, always valid UTF-8
+enum Chunk {
+    Text(String),
+    Mapped(Mmap),
+}
+
+impl Chunk {
+    fn as_str(&self) -> &str {
+        match self {
+            Chunk::Text(text) => text,
+            // SAFETY: mapped chunks are checked to be UTF-8 when created
+            Chunk::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+}
+
+impl InputData {
+    /// Map a file into memory instead of reading it
+    ///
+    /// The pages are loaded by the OS on demand and don't count against the
+    /// heap. The file must not be truncated while mapped, so this isn't used
+    /// for files that are being watched for changes.
+    pub fn map_file(file: &File) -> io::Result<Self> {
+        // SAFETY: the mapping is read-only; see above for modification by other processes
+        let map = unsafe { Mmap::map(file)? };
+        if std::str::from_utf8(&map).is_err() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"));
+        }
+
+        Ok(Self {
+            chunks: vec![Arc::new(Chunk::Mapped(map))],
+        })
+    }
+
+    /// Append another input, keeping the last line of one apart from the first of the next
+    pub fn append(&mut self, other: InputData) {
+        if !self.is_empty() && !other.is_empty() && !self.ends_with_newline() {
+            self.chunks.push(Arc::new(Chunk::Text("\n".to_string())));
+        }
+        self.chunks.extend(other.chunks);
+    }
+
+    /// The pieces of the input in order
+    pub fn chunks(&self) -> impl Iterator<Item = &str> {
+        self.chunks.iter().map(|chunk| chunk.as_str())
+    }
+
+    /// The whole input as one string, copied only if it consists of several chunks
+    pub fn text(&self) -> Cow<'_, str> {
+        match self.chunks.as_slice() {
+            [] => Cow::Borrowed(""),
+            [chunk] => Cow::Borrowed(chunk.as_str()),
+            _ => Cow::Owned(self.chunks().collect()),
+        }
+    }
+
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        self.chunks().map(str::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks().all(str::is_empty)
+    }
+
+    /// Whether any part of the input is memory-mapped
+    pub fn is_mapped(&self) -> bool {
+        self.chunks.iter().any(|chunk| matches!(**chunk, Chunk::Mapped(_)))
+    }
+
+    /// Write the input to `writer` (e.g. jq's stdin) without copying it first
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        for chunk in self.chunks() {
+            writer.write_all(chunk.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn ends_with_newline(&self) -> bool {
+        self.chunks()
+            .filter(|chunk| !chunk.is_empty())
+            .last()
+            .is_some_and(|chunk| chunk.ends_with('\n'))
+    }
+}
+
+impl From<String> for InputData {
+    fn from(text: String) -> Self {
+        Self {
+            chunks: vec![Arc::new(Chunk::Text(text))],
+        }
+    }
+}
+
+impl From<&str> for InputData {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+impl PartialEq for InputData {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.chunks().flat_map(str::bytes).eq(other.chunks().flat_map(str::bytes))
+    }
+}
+
+impl Eq for InputData {}
+
+impl PartialEq<&str> for InputData {
+    fn eq(&self, other: &&str) -> bool {
+        self.text() == *other
+    }
+}
+
+/// Shows the size rather than the (possibly huge) content
+impl fmt::Debug for InputData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputData")
+            .field("len", &self.len())
+            .field("chunks", &self.chunks.len())
+            .field("mapped", &self.is_mapped())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clone_shares_buffer() {
+        let data = InputData::from(r#"{"a": 1}"#);
+        let clone = data.clone();
+
+        assert!(Arc::ptr_eq(&data.chunks[0], &clone.chunks[0]));
+        assert_eq!(clone, r#"{"a": 1}"#);
+    }
+
+    #[test]
+    fn test_append_separates_inputs_with_newline() {
+        let mut data = InputData::from("1");
+        data.append(InputData::from("2\n"));
+        data.append(InputData::from("3"));
+        data.append(InputData::default());
+
+        assert_eq!(data.text(), "1\n2\n3");
+        assert_eq!(data.len(), 5);
+    }
+
+    #[test]
+    fn test_single_chunk_text_is_borrowed() {
+        let data = InputData::from("[1, 2]");
+        assert!(matches!(data.text(), Cow::Borrowed("[1, 2]")));
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut data = InputData::from("{}");
+        data.append(InputData::from("[]"));
+
+        let mut output = Vec::new();
+        data.write_to(&mut output).unwrap();
+        assert_eq!(output, b"{}\n[]");
+    }
+
+    #[test]
+    fn test_map_file() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(r#"{"name": "Zoë"}"#.as_bytes()).unwrap();
+
+        let data = InputData::map_file(&file).unwrap();
+        assert!(data.is_mapped());
+        assert_eq!(data, r#"{"name": "Zoë"}"#);
+    }
+
+    #[test]
+    fn test_map_file_rejects_invalid_utf8() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"\"\xff\"").unwrap();
+
+        let error = InputData::map_file(&file).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,263 @@
+use serde_json::{Map, Number, Value};
+
+use crate::error::JiqError;
+
+/// How CSV/TSV input is split into rows and cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Field separator (`None` = `,` for CSV, tab for TSV)
+    pub delimiter: Option<u8>,
+    /// First row holds the column names used as object keys
+    pub has_headers: bool,
+    /// Quote character (`None` = quotes are ordinary characters)
+    pub quote: Option<u8>,
+    /// Turn numeric and boolean cells into JSON numbers/booleans
+    pub infer_types: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            has_headers: true,
+            quote: Some(b'"'),
+            infer_types: false,
+        }
+    }
+}
+
+/// Convert delimited text to a single JSON array
+///
+/// With a header row each record becomes an object keyed by column name,
+/// otherwise each record becomes an array of cells.
+pub fn delimited_to_json(
+    input: &str,
+    default_delimiter: u8,
+    options: &CsvOptions,
+) -> Result<String, JiqError> {
+    let mut builder = csv::ReaderBuilder::new();
+    builder
+        .delimiter(options.delimiter.unwrap_or(default_delimiter))
+        .has_headers(options.has_headers)
+        .quoting(options.quote.is_some());
+    if let Some(quote) = options.quote {
+        builder.quote(quote);
+    }
+    let mut reader = builder.from_reader(input.as_bytes());
+
+    let headers: Option<Vec<String>> = if options.has_headers {
+        let headers = reader.headers().map_err(csv_error)?;
+        check_unique_headers(headers)?;
+        Some(headers.iter().map(String::from).collect())
+    } else {
+        None
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        // csv errors name the record and line, e.g. "record 3 (line: 4, byte: 52)"
+        let record = record.map_err(csv_error)?;
+        let cells = record.iter().map(|cell| cell_value(cell, options.infer_types));
+
+        let row = match &headers {
+            Some(headers) => Value::Object(
+                headers
+                    .iter()
+                    .cloned()
+                    .zip(cells)
+                    .collect::<Map<_, _>>(),
+            ),
+            None => Value::Array(cells.collect()),
+        };
+        rows.push(row);
+    }
+
+    Ok(Value::Array(rows).to_string())
+}
+
+fn csv_error(error: csv::Error) -> JiqError {
+    JiqError::InvalidCsv(error.to_string())
+}
+
+/// Reject a header row that names two columns alike, as the later column
+/// would silently replace the earlier one in every object
+fn check_unique_headers(headers: &csv::StringRecord) -> Result<(), JiqError> {
+    for (column, name) in headers.iter().enumerate() {
+        if let Some(first) = headers.iter().take(column).position(|earlier| earlier == name) {
+            let line = headers.position().map_or(1, |position| position.line());
+            return Err(JiqError::InvalidCsv(format!(
+                "header row (line: {}): column {} repeats the name \"{}\" of column {} (use --no-header to read rows as arrays)",
+                line,
+                column + 1,
+                name,
+                first + 1
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A cell as JSON, optionally inferring booleans, numbers and empty cells (null)
+fn cell_value(cell: &str, infer_types: bool) -> Value {
+    if !infer_types {
+        return Value::String(cell.to_string());
+    }
+
+    match cell {
+        "" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        // Only strict JSON numbers, so IDs like "007" or "+1" stay strings
+        _ => serde_json::from_str::<Number>(cell)
+            .map_or_else(|_| Value::String(cell.to_string()), Value::Number),
+    }
+}
+
+/// Parse a single-byte delimiter/quote argument; accepts `\t` and `tab` for tabs
+pub fn parse_separator(arg: &str) -> Result<u8, String> {
+    match arg {
+        "\\t" | "tab" => Ok(b'\t'),
+        _ => match arg.as_bytes() {
+            [byte] if byte.is_ascii() => Ok(*byte),
+            _ => Err(format!("expected a single ASCII character, got '{}'", arg)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn convert(input: &str, delimiter: u8, options: &CsvOptions) -> Value {
+        serde_json::from_str(&delimited_to_json(input, delimiter, options).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_header_row_becomes_keys() {
+        let csv = "name,age\nAlice,30\nBob,25\n";
+        let value = convert(csv, b',', &CsvOptions::default());
+
+        assert_eq!(
+            value,
+            json!([{"name": "Alice", "age": "30"}, {"name": "Bob", "age": "25"}])
+        );
+    }
+
+    #[test]
+    fn test_key_order_follows_columns() {
+        let json = delimited_to_json("z,a\n1,2\n", b',', &CsvOptions::default()).unwrap();
+        assert_eq!(json, r#"[{"z":"1","a":"2"}]"#);
+    }
+
+    #[test]
+    fn test_no_header_yields_arrays() {
+        let options = CsvOptions {
+            has_headers: false,
+            ..Default::default()
+        };
+        let value = convert("Alice,30\nBob,25\n", b',', &options);
+
+        assert_eq!(value, json!([["Alice", "30"], ["Bob", "25"]]));
+    }
+
+    #[test]
+    fn test_tsv_and_custom_delimiter() {
+        let value = convert("name\tage\nAlice\t30\n", b'\t', &CsvOptions::default());
+        assert_eq!(value, json!([{"name": "Alice", "age": "30"}]));
+
+        let options = CsvOptions {
+            delimiter: Some(b';'),
+            ..Default::default()
+        };
+        let value = convert("name;age\nAlice;30\n", b',', &options);
+        assert_eq!(value, json!([{"name": "Alice", "age": "30"}]));
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        let csv = "name,note\n\"Smith, J\",\"said \"\"hi\"\"\"\n";
+        let value = convert(csv, b',', &CsvOptions::default());
+
+        assert_eq!(value, json!([{"name": "Smith, J", "note": "said \"hi\""}]));
+    }
+
+    #[test]
+    fn test_quoting_disabled() {
+        let options = CsvOptions {
+            quote: None,
+            ..Default::default()
+        };
+        let value = convert("a,b\n\"x\",y\n", b',', &options);
+
+        assert_eq!(value, json!([{"a": "\"x\"", "b": "y"}]));
+    }
+
+    #[test]
+    fn test_custom_quote_character() {
+        let options = CsvOptions {
+            quote: Some(b'\''),
+            ..Default::default()
+        };
+        let value = convert("a,b\n'x,y',z\n", b',', &options);
+
+        assert_eq!(value, json!([{"a": "x,y", "b": "z"}]));
+    }
+
+    #[test]
+    fn test_type_inference() {
+        let options = CsvOptions {
+            infer_types: true,
+            ..Default::default()
+        };
+        let csv = "id,zip,price,active,note\n1,02134,9.5,true,\n2,+1,-3e2,false,x\n";
+        let value = convert(csv, b',', &options);
+
+        assert_eq!(
+            value,
+            json!([
+                {"id": 1, "zip": "02134", "price": 9.5, "active": true, "note": null},
+                {"id": 2, "zip": "+1", "price": -300.0, "active": false, "note": "x"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_ragged_row_reports_line() {
+        let csv = "a,b\n1,2\n3\n";
+
+        match delimited_to_json(csv, b',', &CsvOptions::default()) {
+            Err(JiqError::InvalidCsv(msg)) => assert!(msg.contains("line: 3"), "{}", msg),
+            other => panic!("Expected InvalidCsv error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_header_is_rejected() {
+        match delimited_to_json("a,b,a\n1,2,3\n", b',', &CsvOptions::default()) {
+            Err(JiqError::InvalidCsv(msg)) => assert!(
+                msg.starts_with("header row (line: 1): column 3 repeats the name \"a\" of column 1"),
+                "{}",
+                msg
+            ),
+            other => panic!("Expected InvalidCsv error, got {:?}", other),
+        }
+
+        // Without a header row the columns have no names to clash
+        let options = CsvOptions {
+            has_headers: false,
+            ..Default::default()
+        };
+        assert_eq!(convert("a,b,a\n1,2,3\n", b',', &options), json!([["a", "b", "a"], ["1", "2", "3"]]));
+    }
+
+    #[test]
+    fn test_parse_separator() {
+        assert_eq!(parse_separator(";"), Ok(b';'));
+        assert_eq!(parse_separator("\\t"), Ok(b'\t'));
+        assert_eq!(parse_separator("tab"), Ok(b'\t'));
+        assert_eq!(parse_separator("\t"), Ok(b'\t'));
+        assert!(parse_separator("ab").is_err());
+        assert!(parse_separator("é").is_err());
+    }
+}
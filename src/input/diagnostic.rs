@@ -0,0 +1,214 @@
+/// Lines shown before and after the offending line
+const CONTEXT_LINES: usize = 2;
+
+/// Widest part of a line shown; longer (e.g. minified) lines are cut around the error
+const MAX_EXCERPT_WIDTH: usize = 100;
+
+/// Describe a JSON parse error with its byte offset and an excerpt of the input
+///
+/// `record` is the 1-based index of the failing document in a stream, if it
+/// isn't the first. The first line is the one-line summary; the excerpt shows
+/// the offending line with a caret under the error and some context around it:
+///
+/// ```text
+/// record 3: expected value at line 3 column 7 (byte offset 24)
+///
+///   2 | {"a": 2}
+///   3 | {"a": }
+///     |       ^
+///   4 | {"a": 4}
+/// ```
+pub fn describe(input: &str, error: &serde_json::Error, record: Option<usize>) -> String {
+    let mut message = String::new();
+    if let Some(record) = record {
+        message.push_str(&format!("record {}: ", record));
+    }
+    message.push_str(&error.to_string());
+
+    // serde_json reports line 0 for errors that aren't tied to a position
+    if error.line() == 0 {
+        return message;
+    }
+
+    let lines: Vec<&str> = input.split('\n').collect();
+    let line_index = (error.line() - 1).min(lines.len() - 1);
+    let line = lines[line_index].trim_end_matches('\r');
+
+    // Columns are 1-based byte positions of the last character read; for
+    // truncated input point just past it, where more was expected
+    let column = if error.is_eof() {
+        line.len()
+    } else {
+        floor_char_boundary(line, error.column().saturating_sub(1))
+    };
+    let line_start: usize = lines[..line_index].iter().map(|line| line.len() + 1).sum();
+    message.push_str(&format!(" (byte offset {})", line_start + column));
+
+    let caret = line[..column].chars().count();
+    let (window_start, window_end) = excerpt_window(line.chars().count(), caret);
+
+    let first = line_index.saturating_sub(CONTEXT_LINES);
+    let last = (line_index + CONTEXT_LINES).min(lines.len() - 1);
+    let gutter = (last + 1).to_string().len();
+
+    message.push('\n');
+    for (index, text) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let text = text.trim_end_matches('\r');
+        // A trailing newline leaves an empty last "line" that isn't worth showing
+        if index != line_index && index == lines.len() - 1 && text.is_empty() {
+            continue;
+        }
+
+        message.push_str(&format!(
+            "\n{:>gutter$} | {}",
+            index + 1,
+            excerpt(text, window_start, window_end)
+        ));
+
+        if index == line_index {
+            // Keep tabs so the caret lines up with the text above it
+            let padding: String = line
+                .chars()
+                .skip(window_start)
+                .take(caret - window_start)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let ellipsis = if window_start > 0 { " " } else { "" };
+            message.push_str(&format!("\n{:>gutter$} | {}{}^", "", ellipsis, padding));
+        }
+    }
+
+    message
+}
+
+/// Range of characters to show so that `caret` is visible on long lines
+fn excerpt_window(line_width: usize, caret: usize) -> (usize, usize) {
+    if line_width <= MAX_EXCERPT_WIDTH {
+        return (0, MAX_EXCERPT_WIDTH);
+    }
+
+    let start = caret
+        .saturating_sub(MAX_EXCERPT_WIDTH / 2)
+        .min(line_width.saturating_sub(MAX_EXCERPT_WIDTH));
+    (start, start + MAX_EXCERPT_WIDTH)
+}
+
+/// The characters of `text` in `start..end`, marking cut-off ends with `…`
+fn excerpt(text: &str, start: usize, end: usize) -> String {
+    let width = text.chars().count();
+    let mut excerpt = String::new();
+    if start > 0 {
+        excerpt.push('…');
+    }
+    excerpt.extend(text.chars().skip(start).take(end - start));
+    if width > end {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+/// Largest char boundary of `text` at or before `index`
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn describe_error(input: &str) -> String {
+        let error = serde_json::from_str::<Value>(input).unwrap_err();
+        describe(input, &error, None)
+    }
+
+    #[test]
+    fn test_caret_points_at_error() {
+        let input = "{\n  \"name\": \"Alice\",\n  \"age\" 30\n}";
+        let message = describe_error(input);
+
+        assert_eq!(
+            message,
+            "expected `:` at line 3 column 9 (byte offset 29)\n\
+             \n\
+             1 | {\n\
+             2 |   \"name\": \"Alice\",\n\
+             3 |   \"age\" 30\n\
+             \x20 |         ^\n\
+             4 | }"
+        );
+        assert_eq!(&input[29..30], "3");
+    }
+
+    #[test]
+    fn test_context_is_limited_around_error() {
+        let mut input = String::from("[\n");
+        for i in 0..20 {
+            input.push_str(&format!("  {},\n", i));
+        }
+        input.push_str("  oops\n]\n");
+        let message = describe_error(&input);
+
+        assert!(message.starts_with("expected value at line 22 column 3"), "{}", message);
+        assert!(message.contains("\n20 |   18,"), "{}", message);
+        assert!(message.contains("\n22 |   oops\n   |   ^"), "{}", message);
+        assert!(message.ends_with("\n23 | ]"), "{}", message);
+        assert!(!message.contains("   17,"), "{}", message);
+    }
+
+    #[test]
+    fn test_record_index_is_prefixed() {
+        let input = "{\"a\": 1}\n{\"a\": }\n";
+        let error = serde_json::Deserializer::from_str(input)
+            .into_iter::<Value>()
+            .find_map(Result::err)
+            .unwrap();
+        let message = describe(input, &error, Some(2));
+
+        assert!(message.starts_with("record 2: expected value at line 2 column 7 (byte offset 15)"), "{}", message);
+        assert!(message.ends_with("2 | {\"a\": }\n  |       ^"), "{}", message);
+    }
+
+    #[test]
+    fn test_long_line_is_cut_around_error() {
+        let items: Vec<String> = (0..500).map(|i| i.to_string()).collect();
+        let input = format!("[{}, ]", items.join(", "));
+        let message = describe_error(&input);
+
+        let lines: Vec<&str> = message.lines().collect();
+        let excerpt = lines[2];
+        let caret = lines[3];
+        assert!(excerpt.starts_with("1 | …"), "{}", excerpt);
+        assert!(excerpt.chars().count() < 120, "{}", excerpt);
+        // The caret sits under the closing bracket following the trailing comma
+        let caret_column = caret.chars().count() - 1;
+        assert_eq!(excerpt.chars().nth(caret_column), Some(']'), "{}", message);
+    }
+
+    #[test]
+    fn test_error_at_end_of_input() {
+        let message = describe_error("{\"a\": [1, 2");
+
+        assert!(message.starts_with("EOF while parsing a list at line 1 column 11 (byte offset 11)"), "{}", message);
+        assert!(message.ends_with("1 | {\"a\": [1, 2\n  |            ^"), "{}", message);
+    }
+
+    #[test]
+    fn test_tabs_keep_caret_aligned() {
+        let message = describe_error("{\n\t\"a\" 1\n}");
+
+        assert!(message.contains("2 | \t\"a\" 1\n  | \t    ^"), "{}", message);
+    }
+
+    #[test]
+    fn test_multibyte_characters_before_error() {
+        let message = describe_error("{\"name\": \"Zoë\" \"x\"}");
+
+        assert!(message.contains("(byte offset 16)"), "{}", message);
+        assert!(message.ends_with("1 | {\"name\": \"Zoë\" \"x\"}\n  |                ^"), "{}", message);
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde_json::{Map, Number, Value};
+
+use crate::error::JiqError;
+use super::compression::Compression;
+use super::delimited::{self, CsvOptions};
+
+/// How the input is parsed before it reaches jq
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputOptions {
+    pub format: InputFormat,
+    /// Only used for CSV/TSV input
+    pub csv: CsvOptions,
+    /// Accept comments, trailing commas and JSON5 in JSON input
+    pub lenient: bool,
+    /// Memory-map large JSON files instead of reading them into memory
+    /// (off for files that may be rewritten in place, like with `--watch`)
+    pub memory_map: bool,
+}
+
+/// Source format of the input, converted to JSON before jq sees it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// Pick the format from the file extension (stdin is read as JSON)
+    #[default]
+    Auto,
+    Json,
+    Yaml,
+    Toml,
+    Csv,
+    Tsv,
+}
+
+impl InputFormat {
+    /// Resolve `Auto` to a concrete format based on the file extension
+    ///
+    /// A compression extension is skipped, so `data.yaml.gz` is YAML.
+    pub fn resolve(self, path: Option<&Path>) -> Self {
+        if self != InputFormat::Auto {
+            return self;
+        }
+
+        match path.and_then(source_extension).as_deref() {
+            Some("yaml" | "yml") => InputFormat::Yaml,
+            Some("toml") => InputFormat::Toml,
+            Some("csv") => InputFormat::Csv,
+            Some("tsv" | "tab") => InputFormat::Tsv,
+            _ => InputFormat::Json,
+        }
+    }
+
+    /// Convert input text in this format to a stream of JSON documents
+    ///
+    /// JSON (and `Auto`) input is returned unchanged. Multi-document YAML
+    /// becomes one JSON document per line, like NDJSON. CSV/TSV becomes a
+    /// single array of rows.
+    pub fn to_json(self, input: String, csv: &CsvOptions) -> Result<String, JiqError> {
+        match self {
+            InputFormat::Auto | InputFormat::Json => Ok(input),
+            InputFormat::Yaml => yaml_to_json(&input),
+            InputFormat::Toml => toml_to_json(&input),
+            InputFormat::Csv => delimited::delimited_to_json(&input, b',', csv),
+            InputFormat::Tsv => delimited::delimited_to_json(&input, b'\t', csv),
+        }
+    }
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InputFormat::Auto => "auto",
+            InputFormat::Json => "json",
+            InputFormat::Yaml => "yaml",
+            InputFormat::Toml => "toml",
+            InputFormat::Csv => "csv",
+            InputFormat::Tsv => "tsv",
+        })
+    }
+}
+
+/// Lowercase extension of the uncompressed file name
+fn source_extension(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    if Compression::is_extension(&extension) {
+        let stem = Path::new(path.file_stem()?);
+        Some(stem.extension()?.to_str()?.to_ascii_lowercase())
+    } else {
+        Some(extension)
+    }
+}
+
+/// Convert (possibly multi-document) YAML to newline-separated JSON
+fn yaml_to_json(input: &str) -> Result<String, JiqError> {
+    use serde::Deserialize;
+
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(input) {
+        // serde_yaml errors already carry "at line L column C"
+        let value = serde_yaml::Value::deserialize(document)
+            .map_err(|e| JiqError::InvalidYaml(e.to_string()))?;
+        documents.push(yaml_value_to_json(value).to_string());
+    }
+
+    Ok(documents.join("\n"))
+}
+
+fn yaml_value_to_json(value: serde_yaml::Value) -> Value {
+    use serde_yaml::Value as Yaml;
+
+    match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => yaml_number_to_json(&n),
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(items) => Value::Array(items.into_iter().map(yaml_value_to_json).collect()),
+        Yaml::Mapping(mapping) => Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (yaml_key_to_string(key), yaml_value_to_json(value)))
+                .collect(),
+        ),
+        // Tags like `!Ref` carry no meaning for jq; keep the tagged value
+        Yaml::Tagged(tagged) => yaml_value_to_json(tagged.value),
+    }
+}
+
+/// JSON has no NaN or infinity, so those become `null`
+fn yaml_number_to_json(number: &serde_yaml::Number) -> Value {
+    if let Some(n) = number.as_i64() {
+        Value::Number(n.into())
+    } else if let Some(n) = number.as_u64() {
+        Value::Number(n.into())
+    } else {
+        number
+            .as_f64()
+            .and_then(Number::from_f64)
+            .map_or(Value::Null, Value::Number)
+    }
+}
+
+/// JSON object keys must be strings; YAML allows any scalar (or even a collection)
+fn yaml_key_to_string(key: serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(s) => s,
+        other => match yaml_value_to_json(other) {
+            Value::String(s) => s,
+            value => value.to_string(),
+        },
+    }
+}
+
+/// Convert a TOML document to a single JSON object
+fn toml_to_json(input: &str) -> Result<String, JiqError> {
+    // toml errors render the offending line with its line and column
+    let table: toml::Table = input
+        .parse()
+        .map_err(|e: toml::de::Error| JiqError::InvalidToml(e.to_string().trim_end().to_string()))?;
+
+    Ok(toml_table_to_json(table).to_string())
+}
+
+fn toml_table_to_json(table: toml::Table) -> Value {
+    Value::Object(
+        table
+            .into_iter()
+            .map(|(key, value)| (key, toml_value_to_json(value)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn toml_value_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(n) => Value::Number(n.into()),
+        toml::Value::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        // Dates have no JSON type; keep their RFC 3339 text
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_value_to_json).collect()),
+        toml::Value::Table(table) => toml_table_to_json(table),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn parse_stream(json: &str) -> Vec<Value> {
+        serde_json::Deserializer::from_str(json)
+            .into_iter::<Value>()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_from_extension() {
+        let resolve = |name: &str| InputFormat::Auto.resolve(Some(&PathBuf::from(name)));
+
+        assert_eq!(resolve("config.yaml"), InputFormat::Yaml);
+        assert_eq!(resolve("config.YML"), InputFormat::Yaml);
+        assert_eq!(resolve("Cargo.toml"), InputFormat::Toml);
+        assert_eq!(resolve("data.json"), InputFormat::Json);
+        assert_eq!(resolve("events.jsonl"), InputFormat::Json);
+        assert_eq!(resolve("export.csv"), InputFormat::Csv);
+        assert_eq!(resolve("export.tsv"), InputFormat::Tsv);
+        assert_eq!(resolve("no_extension"), InputFormat::Json);
+        assert_eq!(resolve("config.yaml.gz"), InputFormat::Yaml);
+        assert_eq!(resolve("export.csv.zst"), InputFormat::Csv);
+        assert_eq!(resolve("events.json.xz"), InputFormat::Json);
+        assert_eq!(resolve("archive.gz"), InputFormat::Json);
+    }
+
+    #[test]
+    fn test_resolve_stdin_defaults_to_json() {
+        assert_eq!(InputFormat::Auto.resolve(None), InputFormat::Json);
+    }
+
+    #[test]
+    fn test_explicit_format_wins_over_extension() {
+        let path = PathBuf::from("data.json");
+        assert_eq!(InputFormat::Yaml.resolve(Some(&path)), InputFormat::Yaml);
+    }
+
+    #[test]
+    fn test_json_is_passed_through() {
+        let input = "{\"b\": 1, \"a\": 2}".to_string();
+        assert_eq!(InputFormat::Json.to_json(input.clone(), &CsvOptions::default()).unwrap(), input);
+    }
+
+    #[test]
+    fn test_yaml_to_json_keeps_key_order() {
+        let yaml = "name: Alice\nage: 30\ntags:\n  - admin\n  - dev\n";
+        let json = InputFormat::Yaml.to_json(yaml.to_string(), &CsvOptions::default()).unwrap();
+
+        assert_eq!(json, r#"{"name":"Alice","age":30,"tags":["admin","dev"]}"#);
+    }
+
+    #[test]
+    fn test_yaml_multi_document_becomes_stream() {
+        let yaml = "---\na: 1\n---\nb: 2\n";
+        let json = InputFormat::Yaml.to_json(yaml.to_string(), &CsvOptions::default()).unwrap();
+
+        assert_eq!(parse_stream(&json), vec![json!({"a": 1}), json!({"b": 2})]);
+    }
+
+    #[test]
+    fn test_yaml_non_string_keys_and_tags() {
+        let yaml = "1: one\ntrue: yes\nref: !Ref bucket\nnan: .nan\n";
+        let json = InputFormat::Yaml.to_json(yaml.to_string(), &CsvOptions::default()).unwrap();
+
+        assert_eq!(
+            parse_stream(&json),
+            vec![json!({"1": "one", "true": "yes", "ref": "bucket", "nan": null})]
+        );
+    }
+
+    #[test]
+    fn test_yaml_error_points_at_source_location() {
+        let yaml = "a: 1\nb: [1, 2\nc: 3\n";
+
+        match InputFormat::Yaml.to_json(yaml.to_string(), &CsvOptions::default()) {
+            Err(JiqError::InvalidYaml(msg)) => assert!(msg.contains("line 3"), "{}", msg),
+            other => panic!("Expected InvalidYaml error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_toml_to_json() {
+        let toml = "title = \"demo\"\n\n[server]\nport = 8080\nstarted = 1979-05-27T07:32:00Z\n";
+        let json = InputFormat::Toml.to_json(toml.to_string(), &CsvOptions::default()).unwrap();
+
+        assert_eq!(
+            parse_stream(&json),
+            vec![json!({
+                "title": "demo",
+                "server": {"port": 8080, "started": "1979-05-27T07:32:00Z"}
+            })]
+        );
+    }
+
+    #[test]
+    fn test_toml_error_points_at_source_location() {
+        let toml = "a = 1\nb = = 2\n";
+
+        match InputFormat::Toml.to_json(toml.to_string(), &CsvOptions::default()) {
+            Err(JiqError::InvalidToml(msg)) => {
+                assert!(msg.contains("line 2"), "{}", msg);
+                assert!(msg.contains("column 5"), "{}", msg);
+            }
+            other => panic!("Expected InvalidToml error, got {:?}", other),
+        }
+    }
+}
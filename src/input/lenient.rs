@@ -0,0 +1,550 @@
+use serde::de::IgnoredAny;
+
+/// What `--lenient` had to change to make the input strict JSON
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Normalization {
+    pub comments: usize,
+    pub trailing_commas: usize,
+    /// Other JSON5 syntax (unquoted keys, single quotes, hex numbers, ...)
+    pub json5: bool,
+    /// `Infinity` and `NaN` literals, which JSON can't represent
+    pub non_finite: usize,
+}
+
+/// What jq prints for infinity; `Infinity` becomes this (or its negation)
+const INFINITY_LITERAL: &str = "1.7976931348623157e+308";
+
+impl Normalization {
+    /// Human-readable summary for the status line, `None` if nothing changed
+    pub fn summary(&self) -> Option<String> {
+        let mut changes = Vec::new();
+        if self.comments > 0 {
+            changes.push(plural(self.comments, "comment"));
+        }
+        if self.trailing_commas > 0 {
+            changes.push(plural(self.trailing_commas, "trailing comma"));
+        }
+
+        let summary = match (changes.is_empty(), self.json5) {
+            (true, false) => return None,
+            (true, true) => "Input converted from JSON5".to_string(),
+            (false, false) => format!("Input normalized: removed {}", changes.join(" and ")),
+            (false, true) => format!("Input converted from JSON5: removed {}", changes.join(" and ")),
+        };
+        if self.non_finite == 0 {
+            return Some(summary);
+        }
+        // Like jq, which has no infinity or NaN literals either
+        Some(format!(
+            "{}; {} replaced (Infinity by ±{}, NaN by null)",
+            summary,
+            plural(self.non_finite, "non-finite number"),
+            INFINITY_LITERAL
+        ))
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Turn JSON with comments, trailing commas or other JSON5 syntax into strict JSON
+///
+/// Comments and trailing commas are blanked out in place, so the text keeps
+/// its key order and formatting and any remaining error still points at the
+/// right line and column. Other JSON5 syntax is then rewritten token by token.
+pub fn normalize(input: String) -> (String, Normalization) {
+    let mut normalization = Normalization::default();
+    let mut bytes = input.into_bytes();
+
+    normalization.comments = blank_comments(&mut bytes);
+    normalization.trailing_commas = blank_trailing_commas(&mut bytes);
+
+    // Only ASCII bytes were replaced by spaces, so this stays valid UTF-8
+    let stripped = String::from_utf8(bytes).expect("blanking ASCII keeps UTF-8 valid");
+    if is_json_stream(&stripped) {
+        return (stripped, normalization);
+    }
+
+    match rewrite_json5(&stripped) {
+        Some((rewritten, non_finite)) if is_json_stream(&rewritten) => {
+            normalization.json5 = true;
+            normalization.non_finite = non_finite;
+            (rewritten, normalization)
+        }
+        // Not JSON5 either; strict validation reports the error
+        _ => (stripped, normalization),
+    }
+}
+
+fn is_json_stream(input: &str) -> bool {
+    serde_json::Deserializer::from_str(input)
+        .into_iter::<IgnoredAny>()
+        .all(|document| document.is_ok())
+}
+
+/// Rewrite JSON5 syntax into JSON: quote unquoted keys, turn single-quoted
+/// strings and JSON5 escapes into JSON strings, hex numbers into decimal and
+/// `+1`, `.5` and `5.` into `1`, `0.5` and `5.0`, and `Infinity` and `NaN`
+/// into what jq prints for them
+///
+/// Decimal digits are copied as they are, so large and precise numbers keep
+/// every digit. Returns the JSON and how many `Infinity` and `NaN` literals
+/// were replaced, `None` if there was nothing to rewrite.
+fn rewrite_json5(input: &str) -> Option<(String, usize)> {
+    let mut output = String::with_capacity(input.len());
+    let mut changed = false;
+    let mut non_finite = 0;
+    let mut rest = input;
+
+    while let Some(ch) = rest.chars().next() {
+        let length = match ch {
+            '"' | '\'' => rewrite_string(rest, &mut output, &mut changed),
+            '+' | '-' | '.' | '0'..='9' => match (ch, non_finite_literal(&rest[1..])) {
+                ('+' | '-', Some((length, literal))) => {
+                    rewrite_non_finite(ch == '-', literal, &mut output, &mut non_finite);
+                    1 + length
+                }
+                _ => rewrite_number(rest, &mut output, &mut changed),
+            },
+            _ if ch.is_alphabetic() || ch == '_' || ch == '$' => {
+                rewrite_identifier(rest, &mut output, &mut changed, &mut non_finite)
+            }
+            _ => {
+                output.push(ch);
+                ch.len_utf8()
+            }
+        };
+        rest = &rest[length..];
+    }
+
+    (changed || non_finite > 0).then_some((output, non_finite))
+}
+
+/// The `Infinity` or `NaN` literal starting `text` and its length
+fn non_finite_literal(text: &str) -> Option<(usize, &'static str)> {
+    let word = identifier(text);
+    ["Infinity", "NaN"].into_iter().find(|literal| word == *literal).map(|literal| (literal.len(), literal))
+}
+
+/// Write `Infinity` (negated if `negative`) or `NaN` as jq prints them
+fn rewrite_non_finite(negative: bool, literal: &str, output: &mut String, non_finite: &mut usize) {
+    *non_finite += 1;
+    match literal {
+        "NaN" => output.push_str("null"),
+        _ => {
+            if negative {
+                output.push('-');
+            }
+            output.push_str(INFINITY_LITERAL);
+        }
+    }
+}
+
+/// The identifier starting `text`
+fn identifier(text: &str) -> &str {
+    let end = text
+        .find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || ch == '$'))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Copy the string literal starting `text` as a JSON string, returning its length
+fn rewrite_string(text: &str, output: &mut String, changed: &mut bool) -> usize {
+    let mut chars = text.char_indices();
+    let quote = chars.next().map_or('"', |(_, quote)| quote);
+    *changed |= quote == '\'';
+    output.push('"');
+
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                let Some((_, escaped)) = chars.next() else {
+                    output.push('\\');
+                    break;
+                };
+                match escaped {
+                    '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'u' => {
+                        output.push('\\');
+                        output.push(escaped);
+                    }
+                    'x' => {
+                        // \xFF is \u00FF
+                        *changed = true;
+                        output.push_str("\\u00");
+                    }
+                    'v' => {
+                        *changed = true;
+                        output.push_str("\\u000b");
+                    }
+                    '0' => {
+                        *changed = true;
+                        output.push_str("\\u0000");
+                    }
+                    // Line continuation
+                    '\n' | '\r' | '\u{2028}' | '\u{2029}' => {
+                        *changed = true;
+                        if escaped == '\r' && chars.clone().next().is_some_and(|(_, next)| next == '\n') {
+                            chars.next();
+                        }
+                    }
+                    // Any other character stands for itself
+                    other => {
+                        *changed = true;
+                        output.push(other);
+                    }
+                }
+            }
+            _ if ch == quote => {
+                output.push('"');
+                return index + ch.len_utf8();
+            }
+            '"' => {
+                *changed = true;
+                output.push_str("\\\"");
+            }
+            _ => output.push(ch),
+        }
+    }
+    // Unterminated; strict validation reports it
+    text.len()
+}
+
+/// Copy the number (or lone sign or dot) starting `text` as a JSON number,
+/// returning its length
+fn rewrite_number(text: &str, output: &mut String, changed: &mut bool) -> usize {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    match bytes[0] {
+        b'+' => {
+            *changed = true;
+            i += 1;
+        }
+        b'-' => {
+            output.push('-');
+            i += 1;
+        }
+        _ => {}
+    }
+
+    let digits = |from: usize, radix: u32| {
+        from + bytes[from..].iter().take_while(|byte| (**byte as char).is_digit(radix)).count()
+    };
+
+    if bytes[i..].starts_with(b"0x") || bytes[i..].starts_with(b"0X") {
+        let end = digits(i + 2, 16);
+        match u128::from_str_radix(&text[i + 2..end], 16) {
+            Ok(number) => {
+                *changed = true;
+                output.push_str(&number.to_string());
+            }
+            // Too large or no digits; strict validation reports it
+            Err(_) => output.push_str(&text[i..end]),
+        }
+        return end;
+    }
+
+    let integer_end = digits(i, 10);
+    let mut end = integer_end;
+    if bytes.get(end) == Some(&b'.') {
+        let fraction_end = digits(end + 1, 10);
+        if integer_end == i && fraction_end == end + 1 {
+            // A lone dot (or sign) isn't a number
+            output.push_str(&text[i..=end]);
+            return end + 1;
+        }
+        if integer_end == i {
+            *changed = true;
+            output.push('0');
+        }
+        output.push_str(&text[i..fraction_end]);
+        if fraction_end == end + 1 {
+            *changed = true;
+            output.push('0');
+        }
+        end = fraction_end;
+    } else {
+        output.push_str(&text[i..integer_end]);
+    }
+
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let mut exponent = end + 1;
+        if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+            exponent += 1;
+        }
+        let exponent_end = digits(exponent, 10);
+        output.push_str(&text[end..exponent_end]);
+        end = exponent_end;
+    }
+    end.max(1)
+}
+
+/// Copy the identifier starting `text`, quoting it if it is an object key,
+/// returning its length
+fn rewrite_identifier(text: &str, output: &mut String, changed: &mut bool, non_finite: &mut usize) -> usize {
+    let word = identifier(text);
+    let end = word.len();
+
+    let is_key = text[end..].trim_start().starts_with(':');
+    if is_key && !matches!(word, "true" | "false" | "null") {
+        *changed = true;
+        output.push('"');
+        output.push_str(word);
+        output.push('"');
+    } else if let Some((_, literal)) = non_finite_literal(word) {
+        rewrite_non_finite(false, literal, output, non_finite);
+    } else {
+        // Literals; anything else is left for strict validation to report
+        output.push_str(word);
+    }
+    end
+}
+
+/// Replace `//` and `/* */` comments outside strings with spaces (newlines are kept)
+fn blank_comments(bytes: &mut [u8]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    let mut quote = None;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+
+        if let Some(q) = quote {
+            if byte == b'\\' {
+                i += 1;
+            } else if byte == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+
+        match (byte, bytes.get(i + 1)) {
+            (b'"' | b'\'', _) => quote = Some(byte),
+            (b'/', Some(b'/')) => {
+                count += 1;
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    bytes[i] = b' ';
+                    i += 1;
+                }
+                continue;
+            }
+            (b'/', Some(b'*')) => {
+                count += 1;
+                let end = bytes[i + 2..]
+                    .windows(2)
+                    .position(|pair| pair == b"*/")
+                    .map_or(bytes.len(), |pos| i + 2 + pos + 2);
+                for byte in &mut bytes[i..end] {
+                    if *byte != b'\n' {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    count
+}
+
+/// Replace commas directly followed by `}` or `]` with a space
+fn blank_trailing_commas(bytes: &mut [u8]) -> usize {
+    let mut count = 0;
+    let mut quote = None;
+    let mut pending_comma = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+
+        if let Some(q) = quote {
+            if byte == b'\\' {
+                i += 1;
+            } else if byte == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+
+        match byte {
+            b'"' | b'\'' => {
+                quote = Some(byte);
+                pending_comma = None;
+            }
+            b',' => pending_comma = Some(i),
+            b'}' | b']' => {
+                if let Some(comma) = pending_comma.take() {
+                    bytes[comma] = b' ';
+                    count += 1;
+                }
+            }
+            _ if byte.is_ascii_whitespace() => {}
+            _ => pending_comma = None,
+        }
+        i += 1;
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn parse(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_strict_json_is_untouched() {
+        let input = r#"{"url": "http://example.com/*x*/", "a": [1, 2]}"#;
+        let (output, normalization) = normalize(input.to_string());
+
+        assert_eq!(output, input);
+        assert_eq!(normalization, Normalization::default());
+        assert_eq!(normalization.summary(), None);
+    }
+
+    #[test]
+    fn test_comments_are_removed() {
+        let input = "{\n  // line comment\n  \"a\": 1, /* block\n comment */ \"b\": 2\n}";
+        let (output, normalization) = normalize(input.to_string());
+
+        assert_eq!(parse(&output), json!({"a": 1, "b": 2}));
+        assert_eq!(normalization.comments, 2);
+        // Line structure is preserved for error locations
+        assert_eq!(output.lines().count(), input.lines().count());
+    }
+
+    #[test]
+    fn test_trailing_commas_are_removed() {
+        let input = "{\"a\": [1, 2,], \"b\": {\"c\": 3,\n},\n}";
+        let (output, normalization) = normalize(input.to_string());
+
+        assert_eq!(parse(&output), json!({"a": [1, 2], "b": {"c": 3}}));
+        assert_eq!(normalization.trailing_commas, 3);
+        assert_eq!(
+            normalization.summary().unwrap(),
+            "Input normalized: removed 3 trailing commas"
+        );
+    }
+
+    #[test]
+    fn test_key_order_is_preserved() {
+        let input = "{\"z\": 1, // last alphabetically\n \"a\": 2,}";
+        let (output, _) = normalize(input.to_string());
+
+        let keys: Vec<_> = parse(&output).as_object().unwrap().keys().cloned().collect();
+        assert_eq!(keys, vec!["z", "a"]);
+    }
+
+    #[test]
+    fn test_comment_markers_inside_strings_are_kept() {
+        let input = r#"{"pattern": "src/**/*.ts", "note": "a, ]", "esc": "\"//"}"#;
+        let (output, normalization) = normalize(input.to_string());
+
+        assert_eq!(output, input);
+        assert_eq!(normalization.comments, 0);
+    }
+
+    #[test]
+    fn test_json5_syntax_is_converted() {
+        let input = "{unquoted: 'single', hex: 0x10, list: [1, 2,], // done\n}";
+        let (output, normalization) = normalize(input.to_string());
+
+        assert_eq!(output, "{\"unquoted\": \"single\", \"hex\": 16, \"list\": [1, 2 ]         \n}");
+        assert!(normalization.json5);
+        assert_eq!(
+            normalization.summary().unwrap(),
+            "Input converted from JSON5: removed 1 comment and 2 trailing commas"
+        );
+    }
+
+    #[test]
+    fn test_json5_numbers_and_strings() {
+        let input = r#"{'it\'s': 'say "hi"\x21', $id: +1, half: .5, whole: 5., neg: -0x1F, exp: 2.5E+3}"#;
+        let (output, normalization) = normalize(input.to_string());
+
+        assert!(normalization.json5);
+        assert_eq!(
+            parse(&output),
+            json!({"it's": "say \"hi\"!", "$id": 1, "half": 0.5, "whole": 5.0, "neg": -31, "exp": 2500.0})
+        );
+    }
+
+    #[test]
+    fn test_json5_keeps_number_literals() {
+        let input = "{big: 9007199254740993, huge: 12345678901234567890, float: 1.0, tiny: 1e-400}";
+        let (output, _) = normalize(input.to_string());
+
+        assert_eq!(
+            output,
+            r#"{"big": 9007199254740993, "huge": 12345678901234567890, "float": 1.0, "tiny": 1e-400}"#
+        );
+    }
+
+    #[test]
+    fn test_json5_infinity_and_nan_are_replaced_like_jq() {
+        let input = "{f: Infinity, g: -Infinity, h: +Infinity, n: [NaN, -NaN], name: 'NaN', Infinity: 1}";
+        let (output, normalization) = normalize(input.to_string());
+
+        assert_eq!(
+            output,
+            r#"{"f": 1.7976931348623157e+308, "g": -1.7976931348623157e+308, "h": 1.7976931348623157e+308, "n": [null, null], "name": "NaN", "Infinity": 1}"#
+        );
+        assert_eq!(normalization.non_finite, 5);
+        assert_eq!(
+            normalization.summary().unwrap(),
+            "Input converted from JSON5; 5 non-finite numbers replaced (Infinity by ±1.7976931348623157e+308, NaN by null)"
+        );
+    }
+
+    #[test]
+    fn test_infinity_in_otherwise_strict_json_is_replaced() {
+        let (output, normalization) = normalize("[1, Infinity]\n{\"a\": NaN}".to_string());
+
+        assert_eq!(output, "[1, 1.7976931348623157e+308]\n{\"a\": null}");
+        assert_eq!(normalization.non_finite, 2);
+        assert!(normalization.summary().unwrap().contains("2 non-finite numbers replaced"));
+    }
+
+    #[test]
+    fn test_invalid_json5_is_left_for_strict_error() {
+        let input = "{a: 1 b: 2}";
+        let (output, normalization) = normalize(input.to_string());
+
+        assert_eq!(output, input);
+        assert!(!normalization.json5);
+    }
+
+    #[test]
+    fn test_ndjson_with_comments_stays_a_stream() {
+        let input = "{\"a\": 1} // first\n{\"a\": 2,}\n";
+        let (output, _) = normalize(input.to_string());
+
+        let documents: Vec<Value> = serde_json::Deserializer::from_str(&output)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(documents, vec![json!({"a": 1}), json!({"a": 2})]);
+    }
+
+    #[test]
+    fn test_invalid_input_keeps_error_location() {
+        let input = "{\n  // comment\n  \"a\": 1,\n  \"b\" 2\n}";
+        let (output, _) = normalize(input.to_string());
+
+        let error = serde_json::from_str::<Value>(&output).unwrap_err();
+        assert_eq!(error.line(), 4);
+    }
+}
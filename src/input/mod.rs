@@ -1 +1,12 @@
+pub mod compression;
+pub mod data;
+pub mod delimited;
+pub mod diagnostic;
+pub mod format;
+pub mod lenient;
+pub mod mode;
 pub mod reader;
+pub mod sample;
+pub mod source;
+pub mod stream;
+pub mod watcher;
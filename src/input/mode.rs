@@ -0,0 +1,131 @@
+use serde_json::Value;
+
+use super::data::InputData;
+use super::sample;
+
+/// How jq should interpret the input (mirrors jq's -s, -n and -R flags)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputMode {
+    /// `--slurp`: read all input values into a single array
+    pub slurp: bool,
+    /// `--null-input`: run the filter once with `null` as input
+    pub null_input: bool,
+    /// `--raw-input`: treat each input line as a string instead of JSON
+    pub raw_input: bool,
+}
+
+impl InputMode {
+    /// jq flags for this mode
+    pub fn jq_flags(&self) -> Vec<&'static str> {
+        let mut flags = Vec::new();
+        if self.slurp {
+            flags.push("--slurp");
+        }
+        if self.null_input {
+            flags.push("--null-input");
+        }
+        if self.raw_input {
+            flags.push("--raw-input");
+        }
+        flags
+    }
+
+    /// Whether input without any JSON document is acceptable
+    pub fn accepts_empty(&self) -> bool {
+        self.slurp || self.null_input
+    }
+
+    /// A sample of the values jq's filter runs against as `.`, used for field autocomplete
+    ///
+    /// A stream of documents (e.g. NDJSON) yields one value per document;
+    /// with `--slurp` it is a single array of every document. Large inputs
+    /// are sampled rather than parsed in full (see `sample`).
+    pub fn filter_inputs(&self, input: &InputData) -> Vec<Value> {
+        if self.null_input {
+            return vec![Value::Null];
+        }
+
+        if self.raw_input {
+            return if self.slurp {
+                vec![sample::text(input)]
+            } else {
+                sample::lines(input)
+            };
+        }
+
+        let documents = sample::documents(input);
+        if self.slurp {
+            vec![Value::Array(documents)]
+        } else {
+            documents
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_mode_has_no_flags() {
+        assert!(InputMode::default().jq_flags().is_empty());
+    }
+
+    #[test]
+    fn test_jq_flags() {
+        let mode = InputMode {
+            slurp: true,
+            null_input: true,
+            raw_input: true,
+        };
+        assert_eq!(mode.jq_flags(), vec!["--slurp", "--null-input", "--raw-input"]);
+    }
+
+    #[test]
+    fn test_filter_inputs_single_document() {
+        let values = InputMode::default().filter_inputs(&r#"{"a": 1}"#.into());
+        assert_eq!(values, vec![json!({"a": 1})]);
+    }
+
+    #[test]
+    fn test_filter_inputs_ndjson_stream() {
+        let values = InputMode::default().filter_inputs(&"{\"a\": 1}\n{\"b\": 2}\n".into());
+        assert_eq!(values, vec![json!({"a": 1}), json!({"b": 2})]);
+    }
+
+    #[test]
+    fn test_filter_inputs_slurp_wraps_documents() {
+        let mode = InputMode {
+            slurp: true,
+            ..Default::default()
+        };
+        let values = mode.filter_inputs(&"{\"a\": 1}\n{\"a\": 2}".into());
+        assert_eq!(values, vec![json!([{"a": 1}, {"a": 2}])]);
+    }
+
+    #[test]
+    fn test_filter_inputs_null_input() {
+        let mode = InputMode {
+            null_input: true,
+            ..Default::default()
+        };
+        assert_eq!(mode.filter_inputs(&r#"{"a": 1}"#.into()), vec![Value::Null]);
+    }
+
+    #[test]
+    fn test_filter_inputs_raw_input() {
+        let mode = InputMode {
+            raw_input: true,
+            ..Default::default()
+        };
+        assert_eq!(mode.filter_inputs(&"first line\nsecond".into()), vec![json!("first line"), json!("second")]);
+
+        let mode = InputMode {
+            raw_input: true,
+            slurp: true,
+            ..Default::default()
+        };
+        assert_eq!(mode.filter_inputs(&"first line\nsecond".into()), vec![json!("first line\nsecond")]);
+    }
+}
@@ -1,8 +1,21 @@
 use std::fs::File;
-use std::io::{self, Read};
-use std::path::Path;
-use serde_json::Value;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use serde::de::IgnoredAny;
 use crate::error::JiqError;
+use super::compression::{self, Compression};
+use super::data::InputData;
+use super::diagnostic;
+use super::format::{InputFormat, InputOptions};
+use super::lenient;
+use super::mode::InputMode;
+use super::source::{InputOrigin, InputSource};
+
+/// Files at least this large are memory-mapped rather than read (if allowed)
+const MEMORY_MAP_MIN_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Enough of a file to recognize compressed data by its magic bytes
+const MAGIC_PEEK_BYTES: u64 = 6;
 
 /// Read JSON from stdin or a file
 pub struct InputReader;
@@ -12,40 +25,205 @@ impl InputReader {
     ///
     /// # Arguments
     /// * `path` - Optional file path. If None, reads from stdin.
+    /// * `options` - Source format; YAML, TOML and CSV/TSV are converted to JSON,
+    ///   and `--lenient` JSON is normalized to strict JSON
+    /// * `mode` - Input interpretation (`--slurp`, `--null-input`, `--raw-input`)
     ///
     /// # Returns
-    /// * `Ok(String)` - Valid JSON string
-    /// * `Err(JiqError)` - If JSON is invalid or IO error occurs
-    pub fn read_json(path: Option<&Path>) -> Result<String, JiqError> {
-        let json_str = match path {
-            Some(file_path) => {
-                // Read from file
-                let mut file = File::open(file_path)?;
-                let mut contents = String::new();
-                file.read_to_string(&mut contents)?;
-                contents
-            }
-            None => {
-                // Read from stdin
-                let mut buffer = String::new();
-                io::stdin().read_to_string(&mut buffer)?;
-                buffer
+    /// * `Ok(InputSource)` - Input text, valid for the given mode, named after the file
+    /// * `Err(JiqError)` - If the input is invalid or IO error occurs
+    pub fn read_json(
+        path: Option<&Path>,
+        options: &InputOptions,
+        mode: &InputMode,
+    ) -> Result<InputSource, JiqError> {
+        let mut source = InputSource::from(String::new());
+        source.name = path.map(|path| path.display().to_string());
+        let mut origin = InputOrigin {
+            path: path.map(Path::to_path_buf),
+            format: InputFormat::Json,
+            ..Default::default()
+        };
+
+        // With --null-input jq only reads input if the filter asks for it,
+        // so don't block waiting for an interactive terminal to hit EOF
+        if mode.null_input && path.is_none() && io::stdin().is_terminal() {
+            return Ok(source);
+        }
+
+        // Large plain JSON files are validated and queried in place
+        if let Some(file_path) = path
+            && let Some(data) = Self::map_large_file(file_path, options, mode)?
+        {
+            Self::validate(&data.text(), mode)?;
+            source.json_input = data;
+            source.origins.push(origin);
+            return Ok(source);
+        }
+
+        let (json_str, compression) = match path {
+            // Read from file
+            Some(file_path) => Self::read_to_string(File::open(file_path)?)?,
+            // Read from stdin
+            None => Self::read_to_string(io::stdin().lock())?,
+        };
+        origin.compression = compression;
+
+        // Raw input is handed to jq as text, whatever it looks like
+        let json_str = if mode.raw_input {
+            json_str
+        } else {
+            match options.format.resolve(path) {
+                InputFormat::Json if options.lenient => {
+                    let (json_str, normalization) = lenient::normalize(json_str);
+                    source.warnings.extend(normalization.summary());
+                    origin.lenient = Some(normalization);
+                    json_str
+                }
+                format => {
+                    origin.format = format;
+                    if matches!(format, InputFormat::Csv | InputFormat::Tsv) {
+                        origin.csv = Some(options.csv.clone());
+                        origin.quoted = options.csv.quote.is_some_and(|quote| json_str.as_bytes().contains(&quote));
+                    }
+                    format.to_json(json_str, &options.csv)?
+                }
             }
         };
 
-        // Validate JSON syntax
-        serde_json::from_str::<Value>(&json_str)
-            .map_err(|e| JiqError::InvalidJson(e.to_string()))?;
+        Self::validate(&json_str, mode)?;
+
+        source.json_input = json_str.into();
+        source.origins.push(origin);
+        Ok(source)
+    }
+
+    /// Memory-map `path` if it is a large file jq can read as-is
+    ///
+    /// Returns `None` for small files and for anything that must be converted
+    /// first (other formats, `--lenient`, compressed data).
+    fn map_large_file(
+        path: &Path,
+        options: &InputOptions,
+        mode: &InputMode,
+    ) -> Result<Option<InputData>, JiqError> {
+        let converted = !mode.raw_input && (options.lenient || options.format.resolve(Some(path)) != InputFormat::Json);
+        if !options.memory_map || converted {
+            return Ok(None);
+        }
+
+        let mut file = File::open(path)?;
+        if file.metadata()?.len() < MEMORY_MAP_MIN_BYTES {
+            return Ok(None);
+        }
+
+        let mut header = Vec::new();
+        (&mut file).take(MAGIC_PEEK_BYTES).read_to_end(&mut header)?;
+        if Compression::detect(&header).is_some() {
+            return Ok(None);
+        }
+
+        Ok(Some(InputData::map_file(&file)?))
+    }
+
+    /// Read every input file (stdin if there are none)
+    ///
+    /// With `tabs` each file becomes its own source; otherwise the files are
+    /// concatenated into a single stream, like `jq . a.json b.json`.
+    pub fn read_sources(
+        paths: &[PathBuf],
+        options: &InputOptions,
+        mode: &InputMode,
+        tabs: bool,
+    ) -> Result<Vec<InputSource>, JiqError> {
+        if paths.is_empty() {
+            return Ok(vec![Self::read_json(None, options, mode)?]);
+        }
+
+        let mut sources = Vec::with_capacity(paths.len());
+        for path in paths {
+            let source = Self::read_json(Some(path), options, mode).map_err(|e| {
+                // Name the failing file when there is more than one
+                if paths.len() > 1 {
+                    JiqError::File {
+                        path: path.clone(),
+                        source: Box::new(e),
+                    }
+                } else {
+                    e
+                }
+            })?;
+            sources.push(source);
+        }
+
+        if tabs || sources.len() == 1 {
+            return Ok(sources);
+        }
+
+        let mut stream = InputSource::new(format!("{} files", sources.len()), InputData::default());
+        for source in sources {
+            // Files keep their own buffers; nothing is copied
+            stream.json_input.append(source.json_input);
+            stream.origins.extend(source.origins);
+
+            let name = source.name.unwrap_or_default();
+            stream
+                .warnings
+                .extend(source.warnings.into_iter().map(|warning| format!("{}: {}", name, warning)));
+        }
+        Ok(vec![stream])
+    }
+
+    /// Read a whole stream as text, decompressing gzip/zstd/bzip2/xz on the fly
+    ///
+    /// Also returns the compression that was detected.
+    fn read_to_string(reader: impl Read) -> Result<(String, Option<Compression>), JiqError> {
+        let (compression, mut reader) = compression::decompressing_reader(reader)?;
+
+        let mut bytes = Vec::new();
+        if let Err(e) = reader.read_to_end(&mut bytes) {
+            return Err(match compression {
+                Some(compression) => JiqError::Decompress(compression.to_string(), e.to_string()),
+                None => e.into(),
+            });
+        }
+
+        let text = String::from_utf8(bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))?;
+        Ok((text, compression))
+    }
+
+    /// Check that the input is something jq will accept in the given mode
+    ///
+    /// Like jq, any whitespace-separated stream of JSON documents (e.g. NDJSON)
+    /// is accepted. Errors show where they are in the input, and errors in
+    /// later documents name the failing record.
+    fn validate(input: &str, mode: &InputMode) -> Result<(), JiqError> {
+        // Raw input is arbitrary text
+        if mode.raw_input {
+            return Ok(());
+        }
+
+        let mut record_count = 0;
+        for document in serde_json::Deserializer::from_str(input).into_iter::<IgnoredAny>() {
+            document.map_err(|e| {
+                let record = (record_count > 0).then_some(record_count + 1);
+                JiqError::InvalidJson(diagnostic::describe(input, &e, record))
+            })?;
+            record_count += 1;
+        }
+
+        if record_count == 0 && !mode.accepts_empty() {
+            return Err(JiqError::InvalidJson("input contains no JSON value".to_string()));
+        }
 
-        Ok(json_str)
+        Ok(())
     }
 
     /// Read and validate JSON from a string (used for testing)
     #[cfg(test)]
-    fn read_json_from_string(json_str: &str) -> Result<String, JiqError> {
-        // Validate JSON syntax
-        serde_json::from_str::<Value>(json_str)
-            .map_err(|e| JiqError::InvalidJson(e.to_string()))?;
+    fn read_json_from_string(json_str: &str, mode: &InputMode) -> Result<String, JiqError> {
+        Self::validate(json_str, mode)?;
 
         Ok(json_str.to_string())
     }
@@ -54,15 +232,16 @@ impl InputReader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::format::InputFormat;
     use std::path::PathBuf;
 
     #[test]
     fn test_read_valid_json_from_file() {
         let path = PathBuf::from("tests/fixtures/simple.json");
-        let result = InputReader::read_json(Some(&path));
+        let result = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default());
 
         assert!(result.is_ok());
-        let json = result.unwrap();
+        let json = result.unwrap().json_input.text().into_owned();
         assert!(json.contains("Alice"));
         assert!(json.contains("Seattle"));
     }
@@ -70,10 +249,10 @@ mod tests {
     #[test]
     fn test_read_array_json_from_file() {
         let path = PathBuf::from("tests/fixtures/array.json");
-        let result = InputReader::read_json(Some(&path));
+        let result = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default());
 
         assert!(result.is_ok());
-        let json = result.unwrap();
+        let json = result.unwrap().json_input.text().into_owned();
         assert!(json.contains("Alice"));
         assert!(json.contains("Bob"));
         assert!(json.contains("Charlie"));
@@ -82,10 +261,10 @@ mod tests {
     #[test]
     fn test_read_nested_json_from_file() {
         let path = PathBuf::from("tests/fixtures/nested.json");
-        let result = InputReader::read_json(Some(&path));
+        let result = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default());
 
         assert!(result.is_ok());
-        let json = result.unwrap();
+        let json = result.unwrap().json_input.text().into_owned();
         assert!(json.contains("TechCorp"));
         assert!(json.contains("engineering"));
     }
@@ -93,7 +272,7 @@ mod tests {
     #[test]
     fn test_invalid_json_returns_error() {
         let path = PathBuf::from("tests/fixtures/invalid.json");
-        let result = InputReader::read_json(Some(&path));
+        let result = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default());
 
         assert!(result.is_err());
         match result {
@@ -107,7 +286,7 @@ mod tests {
     #[test]
     fn test_file_not_found_returns_error() {
         let path = PathBuf::from("tests/fixtures/nonexistent.json");
-        let result = InputReader::read_json(Some(&path));
+        let result = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default());
 
         assert!(result.is_err());
         match result {
@@ -121,7 +300,7 @@ mod tests {
     #[test]
     fn test_valid_json_string() {
         let json = r#"{"name": "Test", "value": 42}"#;
-        let result = InputReader::read_json_from_string(json);
+        let result = InputReader::read_json_from_string(json, &InputMode::default());
 
         assert!(result.is_ok());
         let output = result.unwrap();
@@ -131,7 +310,7 @@ mod tests {
     #[test]
     fn test_invalid_json_string() {
         let json = r#"{"name": "Test", invalid}"#;
-        let result = InputReader::read_json_from_string(json);
+        let result = InputReader::read_json_from_string(json, &InputMode::default());
 
         assert!(result.is_err());
         match result {
@@ -145,7 +324,7 @@ mod tests {
     #[test]
     fn test_empty_json_object() {
         let json = "{}";
-        let result = InputReader::read_json_from_string(json);
+        let result = InputReader::read_json_from_string(json, &InputMode::default());
 
         assert!(result.is_ok());
     }
@@ -153,8 +332,358 @@ mod tests {
     #[test]
     fn test_empty_json_array() {
         let json = "[]";
-        let result = InputReader::read_json_from_string(json);
+        let result = InputReader::read_json_from_string(json, &InputMode::default());
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_ndjson_stream_accepted() {
+        let json = "{\"a\": 1}\n{\"a\": 2}\n{\"b\": [1, 2]}\n";
+        let result = InputReader::read_json_from_string(json, &InputMode::default());
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_invalid_json_error_shows_offending_line() {
+        let json = "{\n  \"a\": 1\n  \"b\": 2\n}";
+        let result = InputReader::read_json_from_string(json, &InputMode::default());
+
+        match result {
+            Err(JiqError::InvalidJson(msg)) => {
+                assert!(msg.starts_with("expected `,` or `}` at line 3 column 3 (byte offset 13)"), "{}", msg);
+                assert!(msg.contains("3 |   \"b\": 2\n  |   ^"), "{}", msg);
+            }
+            _ => panic!("Expected InvalidJson error"),
+        }
+    }
+
+    #[test]
+    fn test_ndjson_reports_first_bad_record_and_line() {
+        let json = "{\"a\": 1}\n{\"a\": 2}\n{\"a\": }\n{\"a\": 4}\n";
+        let result = InputReader::read_json_from_string(json, &InputMode::default());
+
+        match result {
+            Err(JiqError::InvalidJson(msg)) => {
+                assert!(msg.starts_with("record 3:"), "{}", msg);
+                assert!(msg.contains("line 3"), "{}", msg);
+            }
+            _ => panic!("Expected InvalidJson error"),
+        }
+    }
+
+    #[test]
+    fn test_ndjson_fixture_from_file() {
+        let path = PathBuf::from("tests/fixtures/events.jsonl");
+        let result = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default());
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().json_input.text().lines().count(), 4);
+    }
+
+    #[test]
+    fn test_empty_input_rejected_by_default() {
+        let result = InputReader::read_json_from_string("  \n", &InputMode::default());
+
+        assert!(matches!(result, Err(JiqError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn test_slurp_accepts_multiple_documents() {
+        let mode = InputMode {
+            slurp: true,
+            ..Default::default()
+        };
+        let json = "{\"a\": 1}\n{\"a\": 2}";
+
+        assert!(InputReader::read_json_from_string(json, &mode).is_ok());
+        assert!(InputReader::read_json_from_string("", &mode).is_ok());
+    }
+
+    #[test]
+    fn test_slurp_rejects_invalid_document_in_stream() {
+        let mode = InputMode {
+            slurp: true,
+            ..Default::default()
+        };
+        let json = "{\"a\": 1}\n{\"a\": }";
+
+        assert!(matches!(
+            InputReader::read_json_from_string(json, &mode),
+            Err(JiqError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn test_raw_input_accepts_any_text() {
+        let mode = InputMode {
+            raw_input: true,
+            ..Default::default()
+        };
+        let text = "2024-01-01 ERROR something broke\nnot json at all";
+
+        assert!(InputReader::read_json_from_string(text, &mode).is_ok());
+    }
+
+    #[test]
+    fn test_null_input_accepts_empty_input() {
+        let mode = InputMode {
+            null_input: true,
+            ..Default::default()
+        };
+
+        assert!(InputReader::read_json_from_string("", &mode).is_ok());
+    }
+
+    #[test]
+    fn test_yaml_file_converted_to_json() {
+        let path = PathBuf::from("tests/fixtures/simple.yaml");
+        let json = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default()).unwrap().json_input.text().into_owned();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["name"], "Alice");
+        assert_eq!(value["city"], "Seattle");
+    }
+
+    #[test]
+    fn test_toml_file_converted_to_json() {
+        let path = PathBuf::from("tests/fixtures/simple.toml");
+        let json = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default()).unwrap().json_input.text().into_owned();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["name"], "Alice");
+        assert_eq!(value["address"]["city"], "Seattle");
+    }
+
+    #[test]
+    fn test_explicit_format_overrides_extension() {
+        let path = PathBuf::from("tests/fixtures/simple.yaml");
+        let json_options = InputOptions {
+            format: InputFormat::Json,
+            ..Default::default()
+        };
+        let result = InputReader::read_json(Some(&path), &json_options, &InputMode::default());
+
+        assert!(matches!(result, Err(JiqError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn test_raw_input_skips_conversion() {
+        let path = PathBuf::from("tests/fixtures/simple.yaml");
+        let mode = InputMode {
+            raw_input: true,
+            ..Default::default()
+        };
+        let text = InputReader::read_json(Some(&path), &InputOptions::default(), &mode).unwrap().json_input.text().into_owned();
+
+        assert!(text.starts_with("name: Alice"));
+    }
+
+    #[test]
+    fn test_csv_file_converted_to_array_of_objects() {
+        let path = PathBuf::from("tests/fixtures/people.csv");
+        let json = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default()).unwrap().json_input.text().into_owned();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value.as_array().unwrap().len(), 3);
+        assert_eq!(value[0]["name"], "Alice");
+        assert_eq!(value[2]["city"], "Denver");
+    }
+
+    #[test]
+    fn test_compressed_file_is_decompressed() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(b"{\"a\": 1}\n{\"a\": 2}\n").unwrap();
+        encoder.finish().unwrap();
+
+        let json = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default()).unwrap().json_input.text().into_owned();
+        assert_eq!(json, "{\"a\": 1}\n{\"a\": 2}\n");
+    }
+
+    #[test]
+    fn test_compressed_yaml_keeps_format_from_inner_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("simple.yaml.zst");
+        let yaml = std::fs::read("tests/fixtures/simple.yaml").unwrap();
+        std::fs::write(&path, zstd::stream::encode_all(yaml.as_slice(), 0).unwrap()).unwrap();
+
+        let json = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default()).unwrap().json_input.text().into_owned();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["name"], "Alice");
+    }
+
+    #[test]
+    fn test_corrupt_archive_reports_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.json.gz");
+        std::fs::write(&path, [0x1f, 0x8b, 0x08, 0x00, 0xde, 0xad]).unwrap();
+
+        match InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default()) {
+            Err(JiqError::Decompress(format, _)) => assert_eq!(format, "gzip"),
+            other => panic!("Expected Decompress error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_multiple_files_are_concatenated() {
+        let paths = [PathBuf::from("tests/fixtures/simple.json"), PathBuf::from("tests/fixtures/events.jsonl")];
+        let sources =
+            InputReader::read_sources(&paths, &InputOptions::default(), &InputMode::default(), false).unwrap();
+
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].name.as_deref(), Some("2 files"));
+        let documents = InputMode::default().filter_inputs(&sources[0].json_input);
+        assert_eq!(documents.len(), 5);
+    }
+
+    #[test]
+    fn test_origins_record_how_each_file_was_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let gz = dir.path().join("events.jsonl.gz");
+        let json = std::fs::read("tests/fixtures/events.jsonl").unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &json).unwrap();
+        std::fs::write(&gz, encoder.finish().unwrap()).unwrap();
+
+        let csv = PathBuf::from("tests/fixtures/people.csv");
+        let sources = InputReader::read_sources(
+            &[gz.clone(), csv.clone()],
+            &InputOptions::default(),
+            &InputMode::default(),
+            false,
+        )
+        .unwrap();
+
+        let origins = &sources[0].origins;
+        assert_eq!(origins.len(), 2);
+        assert_eq!(origins[0].path.as_deref(), Some(gz.as_path()));
+        assert_eq!(origins[0].compression, Some(Compression::Gzip));
+        assert_eq!(origins[0].format, InputFormat::Json);
+        assert_eq!(origins[1].format, InputFormat::Csv);
+        assert_eq!(origins[1].csv, Some(Default::default()));
+        assert_eq!(origins[1].compression, None);
+    }
+
+    #[test]
+    fn test_tabs_keep_files_separate() {
+        let paths = [PathBuf::from("tests/fixtures/simple.json"), PathBuf::from("tests/fixtures/simple.yaml")];
+        let sources =
+            InputReader::read_sources(&paths, &InputOptions::default(), &InputMode::default(), true).unwrap();
+
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].name.as_deref(), Some("tests/fixtures/simple.json"));
+        assert_eq!(sources[1].name.as_deref(), Some("tests/fixtures/simple.yaml"));
+        // Each file is converted according to its own extension
+        assert!(sources[1].json_input.text().starts_with("{\"name\":\"Alice\""));
+    }
+
+    #[test]
+    fn test_concatenated_raw_files_stay_on_separate_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("a.log");
+        let second = dir.path().join("b.log");
+        std::fs::write(&first, "one").unwrap();
+        std::fs::write(&second, "two\n").unwrap();
+        let mode = InputMode {
+            raw_input: true,
+            ..Default::default()
+        };
+
+        let sources = InputReader::read_sources(&[first, second], &InputOptions::default(), &mode, false).unwrap();
+        assert_eq!(sources[0].json_input, "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_error_in_one_of_several_files_names_it() {
+        let paths = [PathBuf::from("tests/fixtures/simple.json"), PathBuf::from("tests/fixtures/invalid.json")];
+        let result = InputReader::read_sources(&paths, &InputOptions::default(), &InputMode::default(), true);
+
+        match result {
+            Err(JiqError::File { path, source }) => {
+                assert_eq!(path, paths[1]);
+                assert!(matches!(*source, JiqError::InvalidJson(_)));
+            }
+            other => panic!("Expected File error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lenient_mode_normalizes_commented_json() {
+        let path = PathBuf::from("tests/fixtures/tsconfig.json");
+        let options = InputOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let source = InputReader::read_json(Some(&path), &options, &InputMode::default()).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&source.json_input.text()).unwrap();
+        assert_eq!(value["compilerOptions"]["target"], "es2022");
+        assert_eq!(value["include"][0], "src/**/*.ts");
+        assert_eq!(
+            source.warnings,
+            vec!["Input normalized: removed 2 comments and 4 trailing commas"]
+        );
+    }
+
+    #[test]
+    fn test_commented_json_rejected_without_lenient() {
+        let path = PathBuf::from("tests/fixtures/tsconfig.json");
+        let result = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default());
+
+        assert!(matches!(result, Err(JiqError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn test_lenient_warnings_name_the_file_when_concatenated() {
+        let paths = [PathBuf::from("tests/fixtures/simple.json"), PathBuf::from("tests/fixtures/tsconfig.json")];
+        let options = InputOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let sources = InputReader::read_sources(&paths, &options, &InputMode::default(), false).unwrap();
+
+        assert_eq!(sources[0].warnings.len(), 1);
+        assert!(sources[0].warnings[0].starts_with("tests/fixtures/tsconfig.json: "));
+    }
+
+    #[test]
+    fn test_large_json_file_is_memory_mapped_when_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.json");
+        let record = "{\"id\": 1, \"tags\": [\"a\", \"b\"]}\n";
+        let count = MEMORY_MAP_MIN_BYTES as usize / record.len() + 1;
+        std::fs::write(&path, record.repeat(count)).unwrap();
+
+        let mapped = InputOptions {
+            memory_map: true,
+            ..Default::default()
+        };
+        let source = InputReader::read_json(Some(&path), &mapped, &InputMode::default()).unwrap();
+        assert!(source.json_input.is_mapped());
+        assert_eq!(source.json_input.len(), record.len() * count);
+
+        let source = InputReader::read_json(Some(&path), &InputOptions::default(), &InputMode::default()).unwrap();
+        assert!(!source.json_input.is_mapped());
+    }
+
+    #[test]
+    fn test_small_or_converted_files_are_not_memory_mapped() {
+        let options = InputOptions {
+            memory_map: true,
+            ..Default::default()
+        };
+        for fixture in ["tests/fixtures/simple.json", "tests/fixtures/simple.yaml"] {
+            let path = PathBuf::from(fixture);
+            let source = InputReader::read_json(Some(&path), &options, &InputMode::default()).unwrap();
+            assert!(!source.json_input.is_mapped(), "{}", fixture);
+        }
+    }
 }
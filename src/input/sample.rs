@@ -0,0 +1,193 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+
+use super::data::InputData;
+
+/// Documents sampled from a stream before the rest is skipped
+const MAX_DOCUMENTS: usize = 1_000;
+
+/// Input bytes sampled from a stream before the rest is skipped
+/// (checked between documents, so one large document is always sampled)
+const MAX_STREAM_BYTES: usize = 16 * 1024 * 1024;
+
+/// Elements kept from each array
+const MAX_ARRAY_ELEMENTS: usize = 100;
+
+/// Entries kept from each object
+const MAX_OBJECT_ENTRIES: usize = 1_000;
+
+/// Characters kept from each string
+const MAX_STRING_CHARS: usize = 256;
+
+/// A bounded sample of the JSON documents in `input`, for autocomplete
+///
+/// Only the shape of the data matters for field suggestions, so arrays,
+/// objects and strings are cut short while parsing. Memory use stays small
+/// no matter how large the input is.
+pub fn documents(input: &InputData) -> Vec<Value> {
+    let mut documents = Vec::new();
+    let mut bytes_read = 0;
+
+    for chunk in input.chunks() {
+        let mut stream = serde_json::Deserializer::from_str(chunk).into_iter::<Sampled>();
+        while documents.len() < MAX_DOCUMENTS && bytes_read + stream.byte_offset() < MAX_STREAM_BYTES {
+            match stream.next() {
+                Some(Ok(Sampled(value))) => documents.push(value),
+                _ => break,
+            }
+        }
+        bytes_read += stream.byte_offset();
+    }
+
+    documents
+}
+
+/// A bounded sample of the lines in `input` as strings (`--raw-input`)
+pub fn lines(input: &InputData) -> Vec<Value> {
+    input
+        .chunks()
+        .flat_map(str::lines)
+        .take(MAX_DOCUMENTS)
+        .map(|line| Value::String(truncate(line)))
+        .collect()
+}
+
+/// The start of `input` as a single string (`--raw-input --slurp`)
+pub fn text(input: &InputData) -> Value {
+    let start = input.chunks().next().unwrap_or_default();
+    Value::String(truncate(start))
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_STRING_CHARS).collect()
+}
+
+/// A JSON value with its arrays, objects and strings cut to the sample limits
+struct Sampled(Value);
+
+impl<'de> Deserialize<'de> for Sampled {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SampleVisitor).map(Sampled)
+    }
+}
+
+struct SampleVisitor;
+
+impl<'de> Visitor<'de> for SampleVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(truncate(value)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut elements = Vec::new();
+        while elements.len() < MAX_ARRAY_ELEMENTS {
+            match seq.next_element::<Sampled>()? {
+                Some(Sampled(value)) => elements.push(value),
+                None => return Ok(Value::Array(elements)),
+            }
+        }
+        // Skip the rest without building it
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(Value::Array(elements))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if entries.len() < MAX_OBJECT_ENTRIES {
+                let Sampled(value) = map.next_value()?;
+                entries.insert(key, value);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(Value::Object(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_small_input_is_sampled_whole() {
+        let input = InputData::from(r#"{"a": [1, 2.5, null], "b": {"c": true}} "x""#);
+        assert_eq!(
+            documents(&input),
+            vec![json!({"a": [1, 2.5, null], "b": {"c": true}}), json!("x")]
+        );
+    }
+
+    #[test]
+    fn test_large_array_is_cut_short() {
+        let items: Vec<Value> = (0..10_000).map(|i| json!({"id": i})).collect();
+        let input = InputData::from(serde_json::to_string(&items).unwrap());
+
+        let sample = documents(&input);
+        assert_eq!(sample[0].as_array().unwrap().len(), MAX_ARRAY_ELEMENTS);
+        assert_eq!(sample[0][0], json!({"id": 0}));
+    }
+
+    #[test]
+    fn test_long_strings_are_truncated() {
+        let input = InputData::from(format!(r#"{{"blob": "{}"}}"#, "x".repeat(100_000)));
+
+        let sample = documents(&input);
+        assert_eq!(sample[0]["blob"].as_str().unwrap().len(), MAX_STRING_CHARS);
+    }
+
+    #[test]
+    fn test_stream_stops_after_document_limit() {
+        let input = InputData::from("{\"a\": 1}\n".repeat(MAX_DOCUMENTS + 50));
+        assert_eq!(documents(&input).len(), MAX_DOCUMENTS);
+    }
+
+    #[test]
+    fn test_chunks_are_sampled_in_order() {
+        let mut input = InputData::from(r#"{"first": 1}"#);
+        input.append(InputData::from(r#"{"second": 2}"#));
+
+        assert_eq!(documents(&input), vec![json!({"first": 1}), json!({"second": 2})]);
+    }
+
+    #[test]
+    fn test_invalid_document_ends_sample() {
+        let input = InputData::from("{\"a\": 1}\n{\"a\": }\n{\"a\": 3}");
+        assert_eq!(documents(&input), vec![json!({"a": 1})]);
+    }
+
+    #[test]
+    fn test_lines() {
+        let input = InputData::from("first\nsecond\n");
+        assert_eq!(lines(&input), vec![json!("first"), json!("second")]);
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use crate::error::JiqError;
+use super::compression::Compression;
+use super::data::InputData;
+use super::delimited::CsvOptions;
+use super::format::InputFormat;
+use super::lenient::Normalization;
+
+/// One input as handed to jq: a file, several files concatenated, or stdin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputSource {
+    /// Shown in the Results title (`None` for stdin)
+    pub name: Option<String>,
+    /// Input text, already converted to JSON
+    pub json_input: InputData,
+    /// Notes about how the input was read, shown in the status line
+    pub warnings: Vec<String>,
+    /// How each file was read, in order (empty for stdin read as plain JSON)
+    pub origins: Vec<InputOrigin>,
+}
+
+/// Where one input came from and how it was turned into JSON, so a run can
+/// be reproduced (`--record`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputOrigin {
+    /// The file, `None` for stdin
+    pub path: Option<PathBuf>,
+    /// Compression the data was decompressed from
+    pub compression: Option<Compression>,
+    /// Format converted to JSON (`Json` when the text was used as it was)
+    pub format: InputFormat,
+    /// CSV/TSV options, for CSV/TSV input
+    pub csv: Option<CsvOptions>,
+    /// CSV/TSV text containing the quote character
+    pub quoted: bool,
+    /// What `--lenient` changed, if it was on
+    pub lenient: Option<Normalization>,
+}
+
+impl InputSource {
+    pub fn new(name: impl Into<String>, json_input: impl Into<InputData>) -> Self {
+        Self {
+            name: Some(name.into()),
+            json_input: json_input.into(),
+            warnings: Vec::new(),
+            origins: Vec::new(),
+        }
+    }
+}
+
+/// Unnamed input, e.g. read from stdin
+impl From<String> for InputSource {
+    fn from(json_input: String) -> Self {
+        Self {
+            name: None,
+            json_input: json_input.into(),
+            warnings: Vec::new(),
+            origins: Vec::new(),
+        }
+    }
+}
+
+/// Expand glob patterns among the input arguments
+///
+/// Arguments naming an existing file are kept as-is (so odd file names like
+/// `data[1].json` work); patterns the shell left unexpanded are matched here.
+pub fn expand_paths(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, JiqError> {
+    let mut paths = Vec::new();
+
+    for input in inputs {
+        let pattern = input.to_string_lossy();
+        if input.exists() || !is_glob(&pattern) {
+            paths.push(input.clone());
+            continue;
+        }
+
+        let matches = glob::glob(&pattern)
+            .map_err(|e| JiqError::InvalidArgument(format!("{}: {}", pattern, e)))?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+
+        if matches.is_empty() {
+            return Err(JiqError::InvalidArgument(format!("{}: no files match", pattern)));
+        }
+        // glob yields paths in alphabetical order
+        paths.extend(matches);
+    }
+
+    Ok(paths)
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_plain_paths_are_kept_in_order() {
+        let inputs = vec![PathBuf::from("b.json"), PathBuf::from("a.json")];
+        assert_eq!(expand_paths(&inputs).unwrap(), inputs);
+    }
+
+    #[test]
+    fn test_glob_is_expanded_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["prod.json", "staging.json", "notes.txt"] {
+            fs::write(dir.path().join(name), "{}").unwrap();
+        }
+
+        let pattern = dir.path().join("*.json");
+        let paths = expand_paths(&[pattern]).unwrap();
+
+        assert_eq!(
+            paths,
+            vec![dir.path().join("prod.json"), dir.path().join("staging.json")]
+        );
+    }
+
+    #[test]
+    fn test_glob_without_matches_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let pattern = dir.path().join("*.json");
+
+        match expand_paths(&[pattern]) {
+            Err(JiqError::InvalidArgument(msg)) => assert!(msg.contains("no files match"), "{}", msg),
+            other => panic!("Expected InvalidArgument error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_existing_file_with_glob_characters_is_not_expanded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data[1].json");
+        fs::write(&path, "{}").unwrap();
+
+        assert_eq!(expand_paths(std::slice::from_ref(&path)).unwrap(), vec![path]);
+    }
+}
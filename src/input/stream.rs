@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::value::RawValue;
+
+use crate::error::JiqError;
+
+/// Shortest time between two updates: each one re-runs the query and rebuilds
+/// the result cache and autocomplete over every retained record
+const UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Records read incrementally from a never-ending input like `tail -f` (`--follow`)
+///
+/// A background thread parses the stream and hands over the text of each
+/// complete record as it was written, so numbers keep every digit; only the
+/// newest `max_records` are retained.
+pub struct InputStream {
+    /// `None` once the stream has ended
+    receiver: Option<Receiver<Result<String, JiqError>>>,
+    records: VecDeque<String>,
+    max_records: Option<usize>,
+    /// Records arrived since the last `take_update()`
+    changed: bool,
+    last_update: Option<Instant>,
+    error: Option<JiqError>,
+}
+
+impl InputStream {
+    /// Start reading JSON values (or lines with `raw_lines`) from `reader`
+    pub fn spawn(reader: impl Read + Send + 'static, raw_lines: bool, max_records: Option<usize>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let reader = BufReader::new(reader);
+
+            if raw_lines {
+                for line in reader.lines() {
+                    let record = line.map_err(JiqError::from);
+                    let failed = record.is_err();
+                    if sender.send(record).is_err() || failed {
+                        return;
+                    }
+                }
+                return;
+            }
+
+            let values = serde_json::Deserializer::from_reader(reader).into_iter::<Box<RawValue>>();
+            for (index, value) in values.enumerate() {
+                let record = value
+                    .map(|value| value.get().to_string())
+                    .map_err(|e| JiqError::InvalidJson(format!("record {}: {}", index + 1, e)));
+                // The parser can't resync after an error, so it ends the stream
+                let failed = record.is_err();
+                if sender.send(record).is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            receiver: Some(receiver),
+            records: VecDeque::new(),
+            max_records,
+            changed: false,
+            last_update: None,
+            error: None,
+        }
+    }
+
+    /// Move newly arrived records into the buffer, dropping the oldest over the cap
+    pub fn receive(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+
+        loop {
+            match receiver.try_recv() {
+                Ok(Ok(record)) => {
+                    self.records.push_back(record);
+                    if self.max_records.is_some_and(|max| self.records.len() > max) {
+                        self.records.pop_front();
+                    }
+                    self.changed = true;
+                }
+                Ok(Err(e)) => self.error = Some(e),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// The retained records as jq input, if any arrived since the last call
+    ///
+    /// Updates come at most once per `UPDATE_INTERVAL`; records arriving
+    /// sooner wait for the next one.
+    pub fn take_update(&mut self) -> Option<String> {
+        if !self.changed || self.last_update.is_some_and(|at| at.elapsed() < UPDATE_INTERVAL) {
+            return None;
+        }
+        self.changed = false;
+        self.last_update = Some(Instant::now());
+
+        let mut json_input = String::new();
+        for record in &self.records {
+            json_input.push_str(record);
+            json_input.push('\n');
+        }
+        Some(json_input)
+    }
+
+    /// The error that ended the stream (reported once)
+    pub fn take_error(&mut self) -> Option<JiqError> {
+        self.error.take()
+    }
+
+    /// Number of records currently retained
+    pub fn record_count(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether more records may still arrive
+    pub fn is_open(&self) -> bool {
+        self.receiver.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    /// Receive until the reader thread is done
+    fn drain(stream: &mut InputStream) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while stream.is_open() && Instant::now() < deadline {
+            stream.receive();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_json_records_are_received() {
+        let input = "{\"a\": 1}\n{\"a\": 2}\n[1,\n 2]\n";
+        let mut stream = InputStream::spawn(Cursor::new(input), false, None);
+        drain(&mut stream);
+
+        assert_eq!(stream.record_count(), 3);
+        assert_eq!(stream.take_update().unwrap(), "{\"a\": 1}\n{\"a\": 2}\n[1,\n 2]\n");
+        // Nothing new since
+        assert!(stream.take_update().is_none());
+    }
+
+    #[test]
+    fn test_records_keep_their_number_literals() {
+        let input = "12345678901234567890123 {\"id\": 9007199254740993, \"price\": 1.10, \"big\": 1E400}";
+        let mut stream = InputStream::spawn(Cursor::new(input), false, None);
+        drain(&mut stream);
+
+        assert_eq!(
+            stream.take_update().unwrap(),
+            "12345678901234567890123\n{\"id\": 9007199254740993, \"price\": 1.10, \"big\": 1E400}\n"
+        );
+    }
+
+    #[test]
+    fn test_updates_are_throttled() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        let mut stream = InputStream::spawn(reader, false, None);
+        let receive = |stream: &mut InputStream, count: usize| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while stream.record_count() < count && Instant::now() < deadline {
+                stream.receive();
+                thread::sleep(Duration::from_millis(5));
+            }
+        };
+
+        writer.write_all(b"1\n").unwrap();
+        receive(&mut stream, 1);
+        assert_eq!(stream.take_update().unwrap(), "1\n");
+
+        // The next record waits for the interval to pass
+        writer.write_all(b"2\n").unwrap();
+        receive(&mut stream, 2);
+        assert!(stream.take_update().is_none());
+        thread::sleep(UPDATE_INTERVAL);
+        assert_eq!(stream.take_update().unwrap(), "1\n2\n");
+    }
+
+    #[test]
+    fn test_oldest_records_are_dropped_over_cap() {
+        let input = "1 2 3 4 5";
+        let mut stream = InputStream::spawn(Cursor::new(input), false, Some(2));
+        drain(&mut stream);
+
+        assert_eq!(stream.record_count(), 2);
+        assert_eq!(stream.take_update().unwrap(), "4\n5\n");
+    }
+
+    #[test]
+    fn test_raw_lines() {
+        let input = "first line\nsecond {not json\n";
+        let mut stream = InputStream::spawn(Cursor::new(input), true, None);
+        drain(&mut stream);
+
+        assert_eq!(stream.take_update().unwrap(), "first line\nsecond {not json\n");
+    }
+
+    #[test]
+    fn test_invalid_record_ends_stream_with_error() {
+        let input = "{\"a\": 1}\n{\"a\": }\n{\"a\": 3}\n";
+        let mut stream = InputStream::spawn(Cursor::new(input), false, None);
+        drain(&mut stream);
+
+        assert!(!stream.is_open());
+        assert_eq!(stream.record_count(), 1);
+        match stream.take_error() {
+            Some(JiqError::InvalidJson(msg)) => assert!(msg.starts_with("record 2:"), "{}", msg),
+            other => panic!("Expected InvalidJson error, got {:?}", other),
+        }
+        assert!(stream.take_error().is_none());
+    }
+}
@@ -0,0 +1,221 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::error::JiqError;
+use super::format::InputOptions;
+use super::mode::InputMode;
+use super::reader::InputReader;
+use super::source::InputSource;
+
+/// How often watched files are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What identifies a version of a file on disk (`None` if it is missing)
+type FileStamp = Option<(SystemTime, u64)>;
+
+/// The outcome of re-reading the inputs
+type Reload = Result<Vec<InputSource>, JiqError>;
+
+/// Re-reads the input files when they change on disk (`--watch`)
+///
+/// Files are polled rather than subscribed to, so editors and services that
+/// replace a file by renaming a new one over it are picked up as well.
+/// Changed files are re-read on a background thread, so large inputs don't
+/// freeze the UI.
+pub struct InputWatcher {
+    paths: Vec<PathBuf>,
+    options: InputOptions,
+    mode: InputMode,
+    tabs: bool,
+    interval: Duration,
+    stamps: Vec<FileStamp>,
+    last_check: Instant,
+    /// The re-read in progress, if any
+    reload: Option<Receiver<Reload>>,
+}
+
+impl InputWatcher {
+    /// Watch `paths`, re-reading them the same way as on startup
+    ///
+    /// Create it before the startup read: the files are stamped now, so a
+    /// change made during that read is picked up.
+    pub fn new(paths: Vec<PathBuf>, options: InputOptions, mode: InputMode, tabs: bool) -> Self {
+        let stamps = paths.iter().map(|path| stamp(path)).collect();
+        Self {
+            paths,
+            options,
+            mode,
+            tabs,
+            interval: WATCH_INTERVAL,
+            stamps,
+            last_check: Instant::now(),
+            reload: None,
+        }
+    }
+
+    /// Check for changes at a different rate (used in tests)
+    #[cfg(test)]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Start re-reading the inputs if any file changed since the last check,
+    /// and hand over the result once the re-read is done
+    ///
+    /// Returns `None` while nothing changed (or it isn't time to check yet)
+    /// and while a re-read is running, otherwise the freshly read sources or
+    /// the error reading them.
+    pub fn poll(&mut self) -> Option<Reload> {
+        if let Some(reload) = &self.reload {
+            return match reload.try_recv() {
+                Ok(result) => {
+                    self.reload = None;
+                    Some(result)
+                }
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => {
+                    self.reload = None;
+                    None
+                }
+            };
+        }
+
+        if self.last_check.elapsed() < self.interval {
+            return None;
+        }
+        self.last_check = Instant::now();
+
+        // Stamped before reading, so changes during the read are seen next time
+        let stamps: Vec<FileStamp> = self.paths.iter().map(|path| stamp(path)).collect();
+        if stamps == self.stamps {
+            return None;
+        }
+        self.stamps = stamps;
+
+        let (sender, receiver) = mpsc::channel();
+        let (paths, options, mode, tabs) = (self.paths.clone(), self.options.clone(), self.mode, self.tabs);
+        thread::spawn(move || {
+            let _ = sender.send(InputReader::read_sources(&paths, &options, &mode, tabs));
+        });
+        self.reload = Some(receiver);
+        None
+    }
+}
+
+fn stamp(path: &Path) -> FileStamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watcher_for(path: &Path) -> InputWatcher {
+        InputWatcher::new(
+            vec![path.to_path_buf()],
+            InputOptions::default(),
+            InputMode::default(),
+            false,
+        )
+        .with_interval(Duration::ZERO)
+    }
+
+    /// Poll until the background re-read is done
+    fn reloaded(watcher: &mut InputWatcher) -> Option<Reload> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(result) = watcher.poll() {
+                return Some(result);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        None
+    }
+
+    #[test]
+    fn test_unchanged_file_is_not_reread() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        fs::write(&path, r#"{"status": "ok"}"#).unwrap();
+
+        let mut watcher = watcher_for(&path);
+        assert!(watcher.poll().is_none());
+        assert!(watcher.reload.is_none());
+    }
+
+    #[test]
+    fn test_changed_file_is_reread() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        fs::write(&path, r#"{"status": "ok"}"#).unwrap();
+        let mut watcher = watcher_for(&path);
+
+        fs::write(&path, r#"{"status": "degraded"}"#).unwrap();
+
+        // Read in the background
+        assert!(watcher.poll().is_none());
+        assert!(watcher.reload.is_some());
+
+        let sources = reloaded(&mut watcher).unwrap().unwrap();
+        assert!(sources[0].json_input.text().contains("degraded"));
+        // Reported once per change
+        assert!(watcher.poll().is_none());
+        assert!(watcher.reload.is_none());
+    }
+
+    #[test]
+    fn test_invalid_content_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        fs::write(&path, r#"{"status": "ok"}"#).unwrap();
+        let mut watcher = watcher_for(&path);
+
+        fs::write(&path, r#"{"status": "#).unwrap();
+
+        assert!(matches!(reloaded(&mut watcher), Some(Err(JiqError::InvalidJson(_)))));
+    }
+
+    #[test]
+    fn test_deleted_file_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        fs::write(&path, "{}").unwrap();
+        let mut watcher = watcher_for(&path);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(reloaded(&mut watcher), Some(Err(JiqError::Io(_)))));
+    }
+
+    #[test]
+    fn test_checks_are_rate_limited() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        fs::write(&path, "{}").unwrap();
+        let mut watcher = watcher_for(&path).with_interval(Duration::from_secs(60));
+
+        fs::write(&path, r#"{"changed": true}"#).unwrap();
+
+        assert!(watcher.poll().is_none());
+        assert!(watcher.reload.is_none());
+    }
+
+    #[test]
+    fn test_change_before_startup_read_is_seen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        fs::write(&path, r#"{"status": "ok"}"#).unwrap();
+
+        // Stamped, then the file changes while jiq reads it on startup
+        let mut watcher = watcher_for(&path);
+        fs::write(&path, r#"{"status": "changed"}"#).unwrap();
+
+        let sources = reloaded(&mut watcher).unwrap().unwrap();
+        assert!(sources[0].json_input.text().contains("changed"));
+    }
+}
@@ -1,77 +1,489 @@
 use clap::Parser;
 use color_eyre::Result;
-use ratatui::DefaultTerminal;
-use std::path::PathBuf;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::process;
+use std::path::{Path, PathBuf};
 
 mod app;
 mod autocomplete;
+mod config;
 mod editor;
 mod error;
 mod input;
 mod query;
 mod syntax;
+mod terminal;
 
 use app::{App, OutputMode};
-use error::JiqError;
+use config::Config;
+use error::{JiqError, QueryError};
+use input::data::InputData;
+use input::delimited::{self, CsvOptions};
+use input::format::{InputFormat, InputOptions};
+use input::mode::InputMode;
 use input::reader::InputReader;
+use input::source::{self, InputSource};
+use input::stream::InputStream;
+use input::watcher::InputWatcher;
+use query::engine::Engine;
 use query::executor::JqExecutor;
+use query::jaq;
+use query::options::{self as jq_options, JqOptions};
+use query::output::{ColorChoice, OutputFormat, OutputOptions};
+use query::record::{QueryRecord, RecordFormat};
+use query::version::JqVersion;
+use terminal::Tui;
+
+/// Exit status when the input can't be read or parsed
+const EXIT_INVALID_INPUT: i32 = 2;
+
+/// Exit status when the query fails, as with jq
+const EXIT_QUERY_ERROR: i32 = 5;
 
 /// Interactive JSON query tool
 #[derive(Parser, Debug)]
 #[command(version, about = "Interactive JSON query tool with real-time filtering using jq")]
 struct Args {
-    /// Input JSON file (if not provided, reads from stdin)
-    input: Option<PathBuf>,
+    /// Input files or glob patterns (if not provided, reads from stdin)
+    inputs: Vec<PathBuf>,
+
+    /// Open each input file in its own tab instead of concatenating them
+    /// into one stream (switch with Ctrl+N / Ctrl+P)
+    #[arg(long)]
+    tabs: bool,
+
+    /// Reload the input files when they change on disk and re-run the query
+    #[arg(long, requires = "inputs")]
+    watch: bool,
+
+    /// Keep reading stdin and re-run the query as records arrive (like tail -f)
+    #[arg(long, conflicts_with_all = ["inputs", "watch", "tabs"])]
+    follow: bool,
+
+    /// Check that the input parses and exit without starting the TUI;
+    /// errors are shown with their location and exit status 2
+    #[arg(long, conflicts_with_all = ["watch", "follow", "tabs"])]
+    validate: bool,
+
+    /// Run QUERY, print its result and exit without starting the TUI
+    #[arg(short = 'e', long = "expr", value_name = "QUERY", conflicts_with_all = ["watch", "follow", "tabs", "validate"])]
+    expr: Option<String>,
+
+    /// Print the result of the query (`-e`, default `.`) without starting the TUI;
+    /// exits with status 5 if the query fails and 2 if the input is invalid
+    #[arg(long, conflicts_with_all = ["watch", "follow", "tabs", "validate"])]
+    no_tui: bool,
+
+    /// Newest records kept in memory with --follow, 0 for no limit
+    /// (overrides `max_records` in the config file)
+    #[arg(long, value_name = "N")]
+    max_records: Option<u64>,
+
+    /// Format of the input; `auto` picks YAML, TOML or CSV/TSV from the file extension
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = InputFormat::Auto)]
+    input_format: InputFormat,
+
+    /// Accept JSON with comments, trailing commas and other JSON5 syntax,
+    /// normalizing it to strict JSON
+    #[arg(long, visible_alias = "json5")]
+    lenient: bool,
+
+    /// CSV/TSV field separator (default `,` for CSV, tab for TSV; `\t` for tab)
+    #[arg(long, value_name = "CHAR", value_parser = delimited::parse_separator, help_heading = "CSV/TSV input")]
+    delimiter: Option<u8>,
+
+    /// Treat the first CSV/TSV row as data; rows become arrays instead of objects
+    #[arg(long, help_heading = "CSV/TSV input")]
+    no_header: bool,
+
+    /// CSV/TSV quote character
+    #[arg(long, value_name = "CHAR", value_parser = delimited::parse_separator, default_value = "\"", help_heading = "CSV/TSV input")]
+    quote: u8,
+
+    /// Don't treat any character as a CSV/TSV quote
+    #[arg(long, conflicts_with = "quote", help_heading = "CSV/TSV input")]
+    no_quoting: bool,
+
+    /// Convert numeric and true/false CSV/TSV cells to JSON numbers and booleans
+    /// (empty cells become null)
+    #[arg(long, help_heading = "CSV/TSV input")]
+    infer_types: bool,
+
+    /// Read all inputs into one array and use it as the single input (like jq -s)
+    #[arg(short = 's', long)]
+    slurp: bool,
+
+    /// Use `null` as the single input instead of reading it (like jq -n)
+    #[arg(short = 'n', long)]
+    null_input: bool,
+
+    /// Treat each line of input as a string instead of JSON (like jq -R)
+    #[arg(short = 'R', long)]
+    raw_input: bool,
+
+    /// Print strings without quotes (like jq -r)
+    #[arg(short = 'r', long, help_heading = "Output")]
+    raw_output: bool,
+
+    /// Print each result on a single line (like jq -c)
+    #[arg(short = 'c', long, help_heading = "Output")]
+    compact_output: bool,
+
+    /// Indent results with tabs (like jq --tab)
+    #[arg(long, help_heading = "Output")]
+    tab: bool,
+
+    /// Print object keys in sorted order (like jq -S)
+    #[arg(short = 'S', long, help_heading = "Output")]
+    sort_keys: bool,
+
+    /// Write results as jq's JSON, YAML, or CSV/TSV rows from arrays of objects
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = OutputFormat::Json, help_heading = "Output")]
+    output_format: OutputFormat,
+
+    /// Color the printed results; `auto` colors them only on a terminal
+    /// (the TUI is always colored)
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto, help_heading = "Output")]
+    color: ColorChoice,
+
+    /// Instead of the results, print a record of the query, jq options, inputs
+    /// and result: a JSON object, or a jq command line with the result as comments
+    #[arg(long, value_enum, value_name = "FORMAT", help_heading = "Output")]
+    record: Option<RecordFormat>,
+
+    /// Milliseconds to wait after the last keystroke before running the query
+    /// (overrides `debounce_ms` in the config file)
+    #[arg(long, value_name = "MS")]
+    debounce_ms: Option<u64>,
+
+    /// Kill a query after this many milliseconds, 0 for no limit
+    /// (overrides `timeout_ms` in the config file)
+    #[arg(long, value_name = "MS")]
+    timeout_ms: Option<u64>,
+
+    /// Kill a query once its output exceeds this many megabytes, 0 for no limit
+    /// (overrides `max_output_mb` in the config file)
+    #[arg(long, value_name = "MB")]
+    max_output_mb: Option<u64>,
+
+    /// jq implementation that runs queries; `jaq` is built in and needs no jq install
+    /// (each query runs in a jiq worker process, which the limits stop like jq);
+    /// `auto` uses jaq when jq isn't found (overrides `engine` in the config file) [default: auto]
+    #[arg(long, value_enum, value_name = "ENGINE")]
+    engine: Option<Engine>,
+
+    /// jq binary to run, a name looked up in PATH or a path
+    /// (overrides `$JIQ_JQ` and `jq_path` in the config file) [default: jq]
+    #[arg(long, value_name = "PATH")]
+    jq_path: Option<PathBuf>,
+
+    /// Command also given copied text on stdin, e.g. "xclip -selection clipboard",
+    /// for terminals without OSC 52 (overrides `clipboard_command` in the config file)
+    #[arg(long, value_name = "COMMAND")]
+    clipboard_command: Option<String>,
+
+    /// Megabytes of recent query results kept so revisited queries don't run again,
+    /// 0 to disable (overrides `cache_mb` in the config file)
+    #[arg(long, value_name = "MB")]
+    cache_mb: Option<u64>,
+
+    /// Bind $NAME to the string VALUE (like jq --arg)
+    #[arg(long = "arg", num_args = 2, value_names = ["NAME", "VALUE"])]
+    named_args: Vec<String>,
+
+    /// Bind $NAME to the JSON value TEXT (like jq --argjson)
+    #[arg(long = "argjson", num_args = 2, value_names = ["NAME", "TEXT"])]
+    named_json_args: Vec<String>,
+
+    /// Bind $NAME to an array of the JSON values in FILE (like jq --slurpfile)
+    #[arg(long = "slurpfile", num_args = 2, value_names = ["NAME", "FILE"])]
+    slurp_files: Vec<String>,
+
+    /// Bind $NAME to the contents of FILE as a string (like jq --rawfile)
+    #[arg(long = "rawfile", num_args = 2, value_names = ["NAME", "FILE"])]
+    raw_files: Vec<String>,
+
+    /// Positional string arguments, available as $ARGS.positional (like jq --args)
+    #[arg(long = "args", num_args = 1.., value_name = "VALUE", allow_hyphen_values = true)]
+    positional_args: Vec<String>,
+
+    /// Positional JSON arguments, available as $ARGS.positional (like jq --jsonargs)
+    #[arg(long = "jsonargs", num_args = 1.., value_name = "TEXT", allow_hyphen_values = true)]
+    positional_json_args: Vec<String>,
+}
+
+impl Args {
+    /// Collect how the input file is parsed
+    fn input_options(&self) -> InputOptions {
+        InputOptions {
+            format: self.input_format,
+            csv: CsvOptions {
+                delimiter: self.delimiter,
+                has_headers: !self.no_header,
+                quote: (!self.no_quoting).then_some(self.quote),
+                infer_types: self.infer_types,
+            },
+            lenient: self.lenient,
+            memory_map: !self.watch,
+        }
+    }
+
+    /// Whether the query runs without the TUI (`-e` / `--no-tui`)
+    fn is_batch(&self) -> bool {
+        self.no_tui || self.expr.is_some()
+    }
+
+    /// Collect the jq pass-through options
+    fn jq_options(&self) -> JqOptions {
+        JqOptions {
+            named_args: jq_options::pairs(self.named_args.clone()),
+            named_json_args: jq_options::pairs(self.named_json_args.clone()),
+            slurp_files: jq_options::pairs(self.slurp_files.clone()),
+            raw_files: jq_options::pairs(self.raw_files.clone()),
+            positional_args: self.positional_args.clone(),
+            positional_json_args: self.positional_json_args.clone(),
+            input_mode: InputMode {
+                slurp: self.slurp,
+                null_input: self.null_input,
+                raw_input: self.raw_input,
+            },
+            output: OutputOptions {
+                raw: self.raw_output,
+                compact: self.compact_output,
+                tab: self.tab,
+                sort_keys: self.sort_keys,
+                format: self.output_format,
+                // Decided when printing, the TUI is always colored
+                monochrome: false,
+            },
+        }
+    }
 }
 
 fn main() -> Result<()> {
+    // A query for the embedded engine, run by jiq itself (see `Jaq::with_worker`)
+    if env::args().nth(1).as_deref() == Some(jaq::WORKER_FLAG) {
+        if let Err(e) = jaq::run_worker(env::args().skip(2)) {
+            eprint!("{}", e);
+            process::exit(EXIT_QUERY_ERROR);
+        }
+        return Ok(());
+    }
+
     // Install color-eyre panic hook for better error messages
     color_eyre::install()?;
 
     // Parse CLI arguments
     let args = Args::parse();
 
-    // Validate jq binary exists
-    validate_jq_exists()?;
+    // Load config file, then apply CLI overrides
+    let mut config = Config::load()?;
+    if let Some(debounce_ms) = args.debounce_ms {
+        config.debounce_ms = debounce_ms;
+    }
+    if let Some(timeout_ms) = args.timeout_ms {
+        config.timeout_ms = timeout_ms;
+    }
+    if let Some(max_output_mb) = args.max_output_mb {
+        config.max_output_mb = max_output_mb;
+    }
+    if let Some(max_records) = args.max_records {
+        config.max_records = max_records;
+    }
+    if let Some(cache_mb) = args.cache_mb {
+        config.cache_mb = cache_mb;
+    }
+    if let Some(engine) = args.engine {
+        config.engine = engine;
+    }
+    if let Some(jq_path) = &args.jq_path {
+        config.jq_path = Some(jq_path.clone());
+    }
+    if let Some(command) = &args.clipboard_command {
+        config.clipboard_command = Some(command.clone());
+    }
+    config.engine = config.engine.resolve(config.jq_program());
+    config.jq_options = args.jq_options();
+    config.color = args.color;
+    config.record = args.record;
+    check_input(config.jq_options.validate(), args.is_batch())?;
 
-    // Read JSON input
-    let json_input = match InputReader::read_json(args.input.as_deref()) {
-        Ok(json) => json,
-        Err(e) => {
-            eprintln!("Error reading JSON: {:?}", e);
-            return Err(e.into());
-        }
-    };
+    // Checking the input doesn't need jq
+    if args.validate {
+        return validate_inputs(&args, &config);
+    }
+
+    // Validate jq binary exists and detect its version, unless the
+    // embedded engine runs queries
+    if config.engine == Engine::Jq {
+        config.jq_version = validate_jq_exists(config.jq_program())?;
+    }
+
+    if args.follow {
+        return follow_stdin(&args, &config);
+    }
+
+    // Read JSON input (expanding globs the shell left alone)
+    let paths = check_input(source::expand_paths(&args.inputs), args.is_batch())?;
+
+    if args.is_batch() {
+        return run_batch(&args, &config, &paths);
+    }
 
-    // Initialize terminal (handles raw mode, alternate screen, etc.)
-    let terminal = ratatui::init();
+    // Watched files are stamped before they are read, so a change during
+    // the read isn't missed
+    let watcher = args.watch.then(|| {
+        InputWatcher::new(paths.clone(), args.input_options(), config.jq_options.input_mode, args.tabs)
+    });
+    let sources = read_sources(&args, &config, &paths);
+
+    let mut app = App::new(sources, &config);
+    if let Some(watcher) = watcher {
+        app = app.with_input_watcher(watcher);
+    }
+
+    // Initialize terminal (handles raw mode, alternate screen, etc.); it is
+    // /dev/tty when stdout is redirected, so stdout only gets the output below
+    let terminal = terminal::init()?;
 
     // Run the application with JSON input
-    let app = run(terminal, json_input.clone())?;
+    let app = run(terminal, app);
 
     // Restore terminal (automatic cleanup)
-    ratatui::restore();
+    terminal::restore();
+    let app = app?;
 
     // Output results AFTER terminal is restored
-    handle_output(&app, &json_input)?;
+    handle_output(&app, &config)?;
 
     Ok(())
 }
 
-/// Validate that jq binary exists in PATH
-fn validate_jq_exists() -> Result<(), JiqError> {
-    which::which("jq").map_err(|_| JiqError::JqNotFound)?;
+/// Read the inputs as given on the command line, exiting with
+/// `EXIT_INVALID_INPUT` if one can't be read or parsed
+fn read_sources(args: &Args, config: &Config, paths: &[PathBuf]) -> Vec<InputSource> {
+    match InputReader::read_sources(paths, &args.input_options(), &config.jq_options.input_mode, args.tabs) {
+        Ok(sources) => sources,
+        Err(e) => {
+            eprintln!("Error reading input: {}", e);
+            process::exit(EXIT_INVALID_INPUT);
+        }
+    }
+}
+
+/// Pass on the outcome of checking the input paths and argument files; in
+/// batch mode a failure exits with `EXIT_INVALID_INPUT` and a one-line message
+/// instead, as jq does
+fn check_input<T>(result: Result<T, JiqError>, batch: bool) -> Result<T, JiqError> {
+    match result {
+        Err(e) if batch => {
+            eprintln!("Error: {}", e);
+            process::exit(EXIT_INVALID_INPUT);
+        }
+        result => result,
+    }
+}
+
+/// Run the query once and print its result without the TUI (`--no-tui` / `-e`)
+///
+/// The input is read exactly as for the TUI, and the output is what exiting
+/// the TUI with the same query prints.
+fn run_batch(args: &Args, config: &Config, paths: &[PathBuf]) -> Result<()> {
+    // Without --tabs this is a single (possibly concatenated) source
+    let source = read_sources(args, config, paths).remove(0);
+    for warning in &source.warnings {
+        eprintln!("{}", warning);
+    }
+
+    let query = args.expr.as_deref().unwrap_or(".");
+    match config.record {
+        Some(format) => print_record(&source, query, &config.jq_options, format, config)?,
+        None => print_results(&source.json_input, query, &config.jq_options, config),
+    }
     Ok(())
 }
 
-fn run(mut terminal: DefaultTerminal, json_input: String) -> Result<App> {
-    let mut app = App::new(json_input);
+/// Run the TUI on records streaming in from stdin (`--follow`)
+fn follow_stdin(args: &Args, config: &Config) -> Result<()> {
+    let raw_lines = config.jq_options.input_mode.raw_input;
+    if !raw_lines && !matches!(args.input_format, InputFormat::Auto | InputFormat::Json) {
+        return Err(JiqError::InvalidArgument(
+            "--follow reads JSON values or raw lines (-R), not other input formats".to_string(),
+        )
+        .into());
+    }
+
+    let stream = InputStream::spawn(io::stdin(), raw_lines, config.record_limit());
+    let app = App::new(vec![InputSource::new("stdin", String::new())], config).with_input_stream(stream);
+
+    let terminal = terminal::init()?;
+    let app = run(terminal, app);
+    terminal::restore();
+    let app = app?;
+
+    handle_output(&app, config)
+}
+
+/// Read every input as the TUI would and report whether it parses (`--validate`)
+///
+/// All files are checked, so one run lists every broken file; exits with
+/// `EXIT_INVALID_INPUT` if any of them is invalid.
+fn validate_inputs(args: &Args, config: &Config) -> Result<()> {
+    let options = args.input_options();
+    let mode = &config.jq_options.input_mode;
+
+    let paths = source::expand_paths(&args.inputs)?;
+    let results = if paths.is_empty() {
+        vec![("stdin".to_string(), InputReader::read_json(None, &options, mode))]
+    } else {
+        paths
+            .iter()
+            .map(|path| (path.display().to_string(), InputReader::read_json(Some(path), &options, mode)))
+            .collect()
+    };
 
+    let mut failed = false;
+    for (name, result) in results {
+        match result {
+            Ok(source) => {
+                println!("{}: OK", name);
+                for warning in source.warnings {
+                    println!("{}: {}", name, warning);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(EXIT_INVALID_INPUT);
+    }
+    Ok(())
+}
+
+/// Validate that the jq binary exists, returning its version if `jq --version` reports one
+fn validate_jq_exists(program: &Path) -> Result<Option<JqVersion>, JiqError> {
+    let path = which::which(program).map_err(|_| JiqError::JqNotFound(program.display().to_string()))?;
+    Ok(JqVersion::detect(&path))
+}
+
+fn run(mut terminal: Tui, mut app: App) -> Result<App> {
     loop {
+        // Fire due debounced queries and pick up background results
+        app.tick();
+
+        // Copied text goes to the terminal's clipboard, also when the copy
+        // finished in the background
+        app.flush_clipboard(terminal.backend_mut())?;
+
         // Render the UI
         terminal.draw(|frame| app.render(frame))?;
 
-        // Handle events (all logic in app.rs now)
+        // Handle events (waits briefly for input, then returns to poll results)
         app.handle_events()?;
 
         // Check if we should exit
@@ -84,20 +496,20 @@ fn run(mut terminal: DefaultTerminal, json_input: String) -> Result<App> {
 }
 
 /// Handle output after terminal is restored
-fn handle_output(app: &App, json_input: &str) -> Result<()> {
+fn handle_output(app: &App, config: &Config) -> Result<()> {
     match app.output_mode() {
         Some(OutputMode::Results) => {
-            // Execute final query and output results
-            let executor = JqExecutor::new(json_input.to_string());
-            match executor.execute(app.query()) {
-                Ok(result) => println!("{}", result),
-                Err(e) => eprintln!("Error: {}", e),
-            }
+            // Output results for the input file shown when exiting, with the
+            // output options last chosen in the TUI
+            print_results(&app.source().json_input, app.query(), &app.jq_options, config);
         }
         Some(OutputMode::Query) => {
             // Output just the query string
             println!("{}", app.query());
         }
+        Some(OutputMode::Record(format)) => {
+            print_record(app.source(), app.query(), &app.jq_options, format, config)?;
+        }
         None => {
             // No output mode (exited with Ctrl+C or q)
         }
@@ -105,3 +517,70 @@ fn handle_output(app: &App, json_input: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Execute the final query and print its output, exiting with
+/// `EXIT_QUERY_ERROR` if it fails
+///
+/// No limits apply: this is the user's explicit export, same as running jq
+/// directly. Output that doesn't go to a terminal is uncolored (`--color`).
+fn print_results(input: &InputData, query: &str, options: &JqOptions, config: &Config) {
+    let monochrome = !config.color.use_color(io::stdout().is_terminal());
+    match final_result(input, query, options, monochrome, config) {
+        // Every format's output already ends with a newline
+        Ok(result) => print!("{}", result),
+        Err(e) => {
+            eprintln!("Error: {}", e.to_string().trim_end());
+            process::exit(EXIT_QUERY_ERROR);
+        }
+    }
+}
+
+/// Execute the final query and print a record of it (`--record`, `:record`),
+/// exiting with `EXIT_QUERY_ERROR` after the record if the query fails
+///
+/// A shell record shows jq's JSON output, which is what its command prints,
+/// whatever the output format. Fails if no jq command reproduces the input.
+fn print_record(
+    source: &InputSource,
+    query: &str,
+    options: &JqOptions,
+    format: RecordFormat,
+    config: &Config,
+) -> Result<()> {
+    let mut options = options.clone();
+    if format == RecordFormat::Shell {
+        options.output.format = OutputFormat::Json;
+    }
+    let result = final_result(&source.json_input, query, &options, true, config);
+    let failed = result.is_err();
+    let record = QueryRecord {
+        query,
+        options: &options,
+        inputs: &source.origins,
+        result: result.map_err(|e| e.to_string().trim_end().to_string()),
+    };
+
+    let text = record.format(format).map_err(JiqError::InvalidArgument)?;
+    print!("{}", text);
+    if failed {
+        process::exit(EXIT_QUERY_ERROR);
+    }
+    Ok(())
+}
+
+/// The final query's output, without the TUI's limits
+fn final_result(
+    input: &InputData,
+    query: &str,
+    options: &JqOptions,
+    monochrome: bool,
+    config: &Config,
+) -> Result<String, QueryError> {
+    let mut options = options.clone();
+    options.output.monochrome = monochrome;
+
+    JqExecutor::new(input.clone())
+        .with_engine(config.query_engine())
+        .with_options(options)
+        .execute(query)
+}
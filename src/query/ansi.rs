@@ -0,0 +1,45 @@
+use std::borrow::Cow;
+
+/// Remove ANSI escape sequences (jq's colors) from `text`
+///
+/// jq escapes control characters inside strings, so every ESC in its output
+/// starts a color sequence and what remains is plain JSON.
+pub fn strip(text: &str) -> Cow<'_, str> {
+    if !text.contains('\x1b') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\x1b' {
+            plain.push(ch);
+            continue;
+        }
+        // CSI sequence: ESC [ parameters, ended by a byte in '@'..='~'
+        if chars.next() == Some('[') {
+            for ch in chars.by_ref() {
+                if ('@'..='~').contains(&ch) {
+                    break;
+                }
+            }
+        }
+    }
+    Cow::Owned(plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_colors() {
+        let colored = "\x1b[1;39m{\n  \x1b[0m\x1b[34;1m\"a\"\x1b[0m\x1b[1;39m:\x1b[0;32m\"x\"\x1b[0m\x1b[1;39m\n}\x1b[0m";
+        assert_eq!(strip(colored), "{\n  \"a\":\"x\"\n}");
+    }
+
+    #[test]
+    fn test_plain_text_is_borrowed() {
+        assert!(matches!(strip("[1, 2]"), Cow::Borrowed("[1, 2]")));
+    }
+}
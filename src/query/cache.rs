@@ -0,0 +1,178 @@
+use lru::LruCache;
+
+use super::executor::{INPUT_BUILTINS, query_words};
+use crate::error::QueryError;
+
+/// Builtins other than `INPUT_BUILTINS` whose result isn't determined by the
+/// query and input (the clock, messages on stderr), so queries using them or
+/// reading the input stream are run every time
+const VOLATILE_BUILTINS: &[&str] = &["now", "debug", "stderr"];
+
+/// Recent query results, so revisiting a query doesn't run it again
+///
+/// Entries are evicted least recently used first once their total size
+/// exceeds the byte budget. A cache belongs to one executor, i.e. to one
+/// input and one set of jq options, so changing either starts a fresh cache.
+pub struct ResultCache {
+    entries: LruCache<String, Result<String, QueryError>>,
+    /// Approximate memory held by `entries`
+    bytes: usize,
+    budget: usize,
+}
+
+impl ResultCache {
+    /// Create a cache holding up to `budget` bytes of queries and results
+    pub fn new(budget: usize) -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            bytes: 0,
+            budget,
+        }
+    }
+
+    /// The cached result of `query`, marking it as recently used
+    pub fn get(&mut self, query: &str) -> Option<Result<String, QueryError>> {
+        self.entries.get(query).cloned()
+    }
+
+    /// Remember the result of `query`
+    ///
+    /// Only results that running the query again would reproduce are kept:
+    /// output, jq errors, the output limit and unconvertible output, but not cancellation, timeouts
+    /// (which depend on load) or failures to run jq, nor results of queries
+    /// using the clock or the input stream. Results larger than the whole
+    /// budget aren't cached.
+    pub fn insert(&mut self, query: &str, result: &Result<String, QueryError>) {
+        if !is_cacheable(query, result) {
+            return;
+        }
+
+        let size = entry_size(query, result);
+        if size > self.budget {
+            return;
+        }
+
+        if let Some(previous) = self.entries.put(query.to_string(), result.clone()) {
+            self.bytes -= entry_size(query, &previous);
+        }
+        self.bytes += size;
+
+        while self.bytes > self.budget {
+            match self.entries.pop_lru() {
+                Some((query, result)) => self.bytes -= entry_size(&query, &result),
+                None => break,
+            }
+        }
+    }
+
+    /// Number of cached queries (used in tests)
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Approximate memory held by the cached entries (used in tests)
+    #[cfg(test)]
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+fn is_cacheable(query: &str, result: &Result<String, QueryError>) -> bool {
+    let uses = |builtins: &[&str]| query_words(query).any(|word| builtins.contains(&word));
+    !uses(INPUT_BUILTINS)
+        && !uses(VOLATILE_BUILTINS)
+        && matches!(
+        result,
+        Ok(_) | Err(QueryError::Jq(_)) | Err(QueryError::OutputTooLarge(_)) | Err(QueryError::Output(_))
+    )
+}
+
+/// Bytes an entry takes up, counting the text of the query and result
+fn entry_size(query: &str, result: &Result<String, QueryError>) -> usize {
+    let result_len = match result {
+        Ok(output) | Err(QueryError::Jq(output)) => output.len(),
+        Err(_) => 0,
+    };
+    query.len() + result_len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ok_and_error_results_are_cached() {
+        let mut cache = ResultCache::new(1024);
+        cache.insert(".name", &Ok("\"Alice\"\n".to_string()));
+        cache.insert(".[", &Err(QueryError::Jq("syntax error".to_string())));
+
+        assert_eq!(cache.get(".name"), Some(Ok("\"Alice\"\n".to_string())));
+        assert_eq!(cache.get(".["), Some(Err(QueryError::Jq("syntax error".to_string()))));
+        assert_eq!(cache.get(".age"), None);
+    }
+
+    #[test]
+    fn test_transient_failures_are_not_cached() {
+        let mut cache = ResultCache::new(1024);
+        cache.insert("a", &Err(QueryError::Cancelled));
+        cache.insert("b", &Err(QueryError::TimedOut(Duration::from_secs(1))));
+        cache.insert("c", &Err(QueryError::Process("spawn failed".to_string())));
+
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_volatile_queries_are_not_cached() {
+        let mut cache = ResultCache::new(1024);
+        for query in ["now", "now | todate", "input_filename", "input_line_number", "[inputs]", "debug | .a", "stderr"] {
+            cache.insert(query, &Ok("1".to_string()));
+        }
+        assert_eq!(cache.len(), 0);
+
+        // The query's own location is the same every time
+        cache.insert("$__loc__", &Ok("{\"file\":\"<stdin>\",\"line\":1}".to_string()));
+        assert_eq!(cache.len(), 1);
+
+        // Only whole words count
+        cache.insert(".nowhere | .debug_level", &Ok("1".to_string()));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted_over_budget() {
+        let mut cache = ResultCache::new(30);
+        cache.insert("a", &Ok("x".repeat(9)));
+        cache.insert("b", &Ok("x".repeat(9)));
+        cache.insert("c", &Ok("x".repeat(9)));
+        // Using "a" makes "b" the oldest
+        cache.get("a");
+        cache.insert("d", &Ok("x".repeat(9)));
+
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("d").is_some());
+        assert!(cache.bytes() <= 30);
+    }
+
+    #[test]
+    fn test_entry_larger_than_budget_is_skipped() {
+        let mut cache = ResultCache::new(10);
+        cache.insert("small", &Ok("1".to_string()));
+        cache.insert("big", &Ok("x".repeat(100)));
+
+        assert!(cache.get("big").is_none());
+        assert!(cache.get("small").is_some());
+    }
+
+    #[test]
+    fn test_replacing_entry_updates_size() {
+        let mut cache = ResultCache::new(100);
+        cache.insert("q", &Ok("x".repeat(50)));
+        cache.insert("q", &Ok("x".repeat(10)));
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.bytes(), 11);
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+/// Delays query execution until input has been idle for a while
+///
+/// Each `schedule()` pushes the deadline back, so a burst of keystrokes
+/// results in a single execution once typing pauses.
+#[derive(Debug, Clone)]
+pub struct Debouncer {
+    delay: Duration,
+    deadline: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            deadline: None,
+        }
+    }
+
+    /// Request execution after the debounce delay (restarts the timer)
+    pub fn schedule(&mut self) {
+        self.deadline = Some(Instant::now() + self.delay);
+    }
+
+    /// Drop any scheduled execution
+    pub fn cancel(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Check if an execution is scheduled
+    pub fn is_pending(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// Returns true (once) if the scheduled execution is due
+    pub fn take_due(&mut self) -> bool {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.deadline = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Time left until the scheduled execution, if any
+    pub fn time_until_due(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_nothing_scheduled() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(10));
+
+        assert!(!debouncer.is_pending());
+        assert!(!debouncer.take_due());
+        assert_eq!(debouncer.time_until_due(), None);
+    }
+
+    #[test]
+    fn test_zero_delay_is_due_immediately() {
+        let mut debouncer = Debouncer::new(Duration::ZERO);
+        debouncer.schedule();
+
+        assert!(debouncer.take_due());
+        // Only fires once
+        assert!(!debouncer.take_due());
+        assert!(!debouncer.is_pending());
+    }
+
+    #[test]
+    fn test_not_due_before_delay() {
+        let mut debouncer = Debouncer::new(Duration::from_secs(60));
+        debouncer.schedule();
+
+        assert!(debouncer.is_pending());
+        assert!(!debouncer.take_due());
+        assert!(debouncer.time_until_due().unwrap() > Duration::from_secs(59));
+    }
+
+    #[test]
+    fn test_due_after_delay() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(10));
+        debouncer.schedule();
+
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(debouncer.time_until_due(), Some(Duration::ZERO));
+        assert!(debouncer.take_due());
+    }
+
+    #[test]
+    fn test_cancel_clears_schedule() {
+        let mut debouncer = Debouncer::new(Duration::ZERO);
+        debouncer.schedule();
+        debouncer.cancel();
+
+        assert!(!debouncer.is_pending());
+        assert!(!debouncer.take_due());
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::executor::{CancelToken, ExecutionLimits};
+use super::options::JqOptions;
+use super::version::JqVersion;
+use crate::error::QueryError;
+use crate::input::data::InputData;
+
+/// Something that can run a jq filter against an input
+///
+/// Implementations produce jq's colored output (`jq --color-output`), or
+/// plain output if `options.output` is monochrome, and enforce the limits
+/// and cancellation themselves.
+pub trait QueryEngine: Send + Sync {
+    fn execute(
+        &self,
+        query: &str,
+        input: &InputData,
+        options: &JqOptions,
+        limits: &ExecutionLimits,
+        cancel: &CancelToken,
+    ) -> Result<String, QueryError>;
+
+    /// The jq implementation behind this engine, deciding which builtins exist
+    fn dialect(&self) -> Dialect;
+}
+
+/// Which jq implementation runs queries (`--engine` / `engine` in the config file)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// The jq binary if it can be found, otherwise the embedded engine
+    #[default]
+    Auto,
+    /// Run the jq binary for every query
+    Jq,
+    /// Embedded pure-Rust jq implementation; no jq install needed
+    Jaq,
+}
+
+impl Engine {
+    /// Pick a concrete engine for `Auto`, looking for `jq_program`
+    pub fn resolve(self, jq_program: &Path) -> Self {
+        match self {
+            Engine::Auto if which::which(jq_program).is_ok() => Engine::Jq,
+            Engine::Auto => Engine::Jaq,
+            engine => engine,
+        }
+    }
+}
+
+/// The language variant queries are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// jq, with the release if it could be detected
+    Jq(Option<JqVersion>),
+    /// The embedded jaq engine
+    Jaq,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect::Jq(None)
+    }
+}
+
+/// `jq 1.7.1`, `jq` or `jaq`, as shown in the help line
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dialect::Jq(Some(version)) => write!(f, "jq {}", version),
+            Dialect::Jq(None) => f.write_str("jq"),
+            Dialect::Jaq => f.write_str("jaq"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Settings {
+        engine: Engine,
+    }
+
+    #[test]
+    fn test_explicit_engines_resolve_to_themselves() {
+        let jq = Path::new("jq");
+        assert_eq!(Engine::Jq.resolve(jq), Engine::Jq);
+        assert_eq!(Engine::Jaq.resolve(jq), Engine::Jaq);
+        assert_ne!(Engine::Auto.resolve(jq), Engine::Auto);
+    }
+
+    #[test]
+    fn test_auto_falls_back_to_jaq_without_jq() {
+        assert_eq!(Engine::Auto.resolve(Path::new("/nonexistent/jq")), Engine::Jaq);
+    }
+
+    #[test]
+    fn test_engine_names_in_config() {
+        let settings: Settings = toml::from_str("engine = \"jaq\"").unwrap();
+        assert_eq!(settings.engine, Engine::Jaq);
+        assert!(toml::from_str::<Settings>("engine = \"gojq\"").is_err());
+    }
+
+    #[test]
+    fn test_dialect_display() {
+        assert_eq!(Dialect::Jq(Some(JqVersion::new(1, 7, 1))).to_string(), "jq 1.7.1");
+        assert_eq!(Dialect::Jq(None).to_string(), "jq");
+        assert_eq!(Dialect::Jaq.to_string(), "jaq");
+    }
+}
@@ -1,15 +1,117 @@
-use std::io::Write;
-use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::ansi;
+use super::cache::ResultCache;
+use super::engine::QueryEngine;
+use super::jq_process::JqProcess;
+use super::options::JqOptions;
+use super::output::OutputFormat;
+use crate::autocomplete::find_last_top_level_pipe;
+use crate::error::QueryError;
+use crate::input::data::InputData;
+use crate::input::mode::InputMode;
+
+/// How often a running query is checked for completion, cancellation or limits
+pub(crate) const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Builtins that read the input stream themselves, so a stage using them
+/// can't be run against the output of the stages before it, nor its result
+/// be cached
+pub(crate) const INPUT_BUILTINS: &[&str] = &["input", "inputs", "input_filename", "input_line_number"];
+
+/// Builtins producing or testing NaN and infinities, or returning NaN outside
+/// their domain: jq prints NaN as null and infinities as the largest double,
+/// so a stage reading them back gets different values
+const NON_FINITE_BUILTINS: &[&str] = &[
+    "nan", "infinite", "isnan", "isinfinite", "isnormal", "sqrt", "pow", "log", "log2", "log10", "log1p", "acos",
+    "asin", "acosh", "atanh", "fmod",
+];
+
+/// How jq and jaq print infinities (and finite numbers rounding to the largest double)
+const MAX_DOUBLE: &str = "1.7976931348623157e+308";
+
+/// Shared flag used to abort an in-flight jq execution
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation; the running query will be stopped
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Resource limits applied to a single jq execution
+///
+/// `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    /// Wall-clock time a query may run before it is stopped
+    pub timeout: Option<Duration>,
+    /// Maximum bytes of output captured before the query is stopped
+    pub max_output_bytes: Option<usize>,
+}
 
 /// Execute jq queries against JSON input
 pub struct JqExecutor {
-    json_input: String,
+    json_input: InputData,
+    options: JqOptions,
+    limits: ExecutionLimits,
+    engine: Arc<dyn QueryEngine>,
+    /// Recent results; input and options are fixed per executor, so a new
+    /// executor (and cache) is built whenever they change
+    cache: Option<Mutex<ResultCache>>,
 }
 
 impl JqExecutor {
     /// Create a new JQ executor with JSON input
-    pub fn new(json_input: String) -> Self {
-        Self { json_input }
+    ///
+    /// The input is shared, not copied: executors for the same input can be
+    /// created freely.
+    pub fn new(json_input: impl Into<InputData>) -> Self {
+        Self {
+            json_input: json_input.into(),
+            options: JqOptions::default(),
+            limits: ExecutionLimits::default(),
+            engine: Arc::new(JqProcess::default()),
+            cache: None,
+        }
+    }
+
+    /// Choose the jq implementation that runs queries (`jq` from PATH by default)
+    pub fn with_engine(mut self, engine: Arc<dyn QueryEngine>) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Set the jq options (`--arg`, `--args`, ...) forwarded to each execution
+    pub fn with_options(mut self, options: JqOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the time and output size limits for each execution
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Keep up to `budget` bytes of recent results, answering repeated
+    /// queries without running them (0 disables the cache)
+    pub fn with_cache(mut self, budget: usize) -> Self {
+        self.cache = (budget > 0).then(|| Mutex::new(ResultCache::new(budget)));
+        self
     }
 
     /// Execute a jq query and return results or error
@@ -19,127 +121,719 @@ impl JqExecutor {
     ///
     /// # Returns
     /// * `Ok(String)` - Filtered JSON output with colors preserved
-    /// * `Err(String)` - jq error message
-    pub fn execute(&self, query: &str) -> Result<String, String> {
-        // Empty query defaults to identity filter
-        let query = if query.trim().is_empty() { "." } else { query };
-
-        // Spawn jq process with color output
-        let mut child = Command::new("jq")
-            .arg("--color-output")
-            .arg(query)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn jq: {}", e))?;
-
-        // Write JSON to jq's stdin
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(self.json_input.as_bytes())
-                .map_err(|e| format!("Failed to write to jq stdin: {}", e))?;
+    /// * `Err(QueryError)` - jq error message or exceeded limit
+    pub fn execute(&self, query: &str) -> Result<String, QueryError> {
+        self.execute_cancellable(query, &CancelToken::new())
+    }
+
+    /// Execute a jq query, stopping it if `cancel` is triggered or one of
+    /// the execution limits is exceeded
+    pub fn execute_cancellable(&self, query: &str, cancel: &CancelToken) -> Result<String, QueryError> {
+        let deadline = self.limits.timeout.map(|timeout| Instant::now() + timeout);
+        self.execute_until(query, deadline, cancel)
+    }
+
+    /// Execute a query that must finish by `deadline`, which all stages of a
+    /// pipeline share
+    fn execute_until(&self, query: &str, deadline: Option<Instant>, cancel: &CancelToken) -> Result<String, QueryError> {
+        // Empty query defaults to identity filter; surrounding whitespace
+        // doesn't change the result
+        let query = match query.trim() {
+            "" => ".",
+            query => query,
+        };
+
+        if let Some(result) = self.cached(|cache| cache.get(query)).flatten() {
+            return result;
+        }
+
+        // Pipelines are evaluated stage by stage only when the intermediate
+        // results can be kept and read back as JSON
+        let staged = self.cache.is_some() && self.options.output.preserves_values();
+        let result = match split_last_stage(query) {
+            Some((prefix, tail)) if staged => self.execute_tail(query, prefix, tail, deadline, cancel),
+            _ => self.run(query, &self.json_input, &self.options, deadline, cancel),
+        };
+        // --rawfile and --slurpfile are read on every run and may have changed
+        if !self.reads_argument_files(query) {
+            self.cached(|cache| cache.insert(query, &result));
         }
+        result
+    }
 
-        // Wait for jq to finish and capture output
-        let output = child
-            .wait_with_output()
-            .map_err(|e| format!("Failed to read jq output: {}", e))?;
+    /// Whether `query` uses a variable read from a file (`--rawfile`, `--slurpfile`)
+    fn reads_argument_files(&self, query: &str) -> bool {
+        let options = &self.options;
+        let mut files = options.slurp_files.iter().chain(&options.raw_files);
+        files.any(|(name, _)| query_words(query).any(|word| word.strip_prefix('$') == Some(name.as_str())))
+    }
+
+    /// Run the last stage of `query` against the output of `prefix`, taken
+    /// from the cache or computed (and cached) first
+    ///
+    /// Editing the end of a long pipeline then only re-runs its last stage.
+    fn execute_tail(
+        &self,
+        query: &str,
+        prefix: &str,
+        tail: &str,
+        deadline: Option<Instant>,
+        cancel: &CancelToken,
+    ) -> Result<String, QueryError> {
+        match self.execute_until(prefix, deadline, cancel) {
+            Ok(output) => {
+                let output = ansi::strip(&output);
+                // Infinities were printed as the largest double
+                if output.contains(MAX_DOUBLE) {
+                    return self.run(query, &self.json_input, &self.options, deadline, cancel);
+                }
+                let input = InputData::from(output.into_owned());
+                // --slurp, --null-input and --raw-input were applied by the first stage
+                let options = JqOptions {
+                    input_mode: InputMode::default(),
+                    ..self.options.clone()
+                };
+                self.run(tail, &input, &options, deadline, cancel)
+            }
+            // Large intermediate output doesn't mean the final output is too large
+            Err(QueryError::OutputTooLarge(_)) => self.run(query, &self.json_input, &self.options, deadline, cancel),
+            Err(error) => Err(error),
+        }
+    }
 
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    /// Run `query` on the engine with the time left until `deadline`,
+    /// converting its output to YAML or CSV/TSV if asked
+    fn run(
+        &self,
+        query: &str,
+        input: &InputData,
+        options: &JqOptions,
+        deadline: Option<Instant>,
+        cancel: &CancelToken,
+    ) -> Result<String, QueryError> {
+        let limits = self.remaining_limits(deadline)?;
+        let format = options.output.format;
+        let result = if format == OutputFormat::Json {
+            self.engine.execute(query, input, options, &limits, cancel)
         } else {
-            Err(String::from_utf8_lossy(&output.stderr).to_string())
+            let options = JqOptions {
+                output: options.output.json_source(),
+                ..options.clone()
+            };
+            self.engine
+                .execute(query, input, &options, &limits, cancel)
+                .and_then(|json| format.convert(&json).map_err(QueryError::Output))
+        };
+
+        // A timeout is reported as configured, not as what was left of it
+        match (result, self.limits.timeout) {
+            (Err(QueryError::TimedOut(_)), Some(timeout)) => Err(QueryError::TimedOut(timeout)),
+            (result, _) => result,
+        }
+    }
+
+    /// The limits for a run that must finish by `deadline`
+    fn remaining_limits(&self, deadline: Option<Instant>) -> Result<ExecutionLimits, QueryError> {
+        let (Some(timeout), Some(deadline)) = (self.limits.timeout, deadline) else {
+            return Ok(self.limits);
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(QueryError::TimedOut(timeout));
         }
+        Ok(ExecutionLimits {
+            timeout: Some(remaining),
+            ..self.limits
+        })
+    }
+
+    /// Run `f` on the result cache, if there is one
+    fn cached<T>(&self, f: impl FnOnce(&mut ResultCache) -> T) -> Option<T> {
+        let cache = self.cache.as_ref()?;
+        // A panic while holding the lock leaves the cache usable
+        let mut cache = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Some(f(&mut cache))
     }
 }
 
+/// The names and `$variables` in `query` (and other runs of word characters)
+pub(crate) fn query_words(query: &str) -> impl Iterator<Item = &str> {
+    query
+        .split(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '$'))
+        .filter(|word| !word.is_empty())
+}
+
+/// Split `query` at its last top-level pipe into `(prefix, tail)`, if the
+/// tail can be run against the prefix's output
+fn split_last_stage(query: &str) -> Option<(&str, &str)> {
+    let uses = |builtins: &[&str]| query_words(query).any(|word| builtins.contains(&word));
+    if uses(INPUT_BUILTINS) || uses(NON_FINITE_BUILTINS) {
+        return None;
+    }
+
+    let pipe = find_last_top_level_pipe(query)?;
+    let (prefix, tail) = (query[..pipe].trim(), query[pipe + 1..].trim());
+    // An incomplete pipeline is run as a whole to get jq's syntax error
+    (!prefix.is_empty() && !tail.is_empty()).then_some((prefix, tail))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::query::jaq::Jaq;
+    use crate::query::output::OutputOptions;
+
+    /// The executor suite, run once per engine
+    macro_rules! engine_tests {
+        ($engine:expr) => {
+            use super::super::*;
+            use std::time::Instant;
+
+            fn executor(json_input: impl Into<InputData>) -> JqExecutor {
+                JqExecutor::new(json_input).with_engine(Arc::new($engine))
+            }
+
+            #[test]
+            fn test_identity_filter() {
+                let json = r#"{"name": "Alice", "age": 30}"#;
+                let executor = executor(json.to_string());
+                let result = executor.execute(".");
+
+                assert!(result.is_ok());
+                let output = result.unwrap();
+                assert!(output.contains("Alice"));
+                assert!(output.contains("30"));
+            }
+
+            #[test]
+            fn test_empty_query_defaults_to_identity() {
+                let json = r#"{"name": "Bob"}"#;
+                let executor = executor(json.to_string());
+                let result = executor.execute("");
+
+                assert!(result.is_ok());
+                let output = result.unwrap();
+                assert!(output.contains("Bob"));
+            }
+
+            #[test]
+            fn test_field_selection() {
+                let json = r#"{"name": "Charlie", "age": 25, "city": "NYC"}"#;
+                let executor = executor(json.to_string());
+                let result = executor.execute(".name");
+
+                assert!(result.is_ok());
+                let output = result.unwrap();
+                assert!(output.contains("Charlie"));
+                assert!(!output.contains("NYC"));
+            }
+
+            #[test]
+            fn test_array_iteration() {
+                let json = r#"[{"id": 1}, {"id": 2}, {"id": 3}]"#;
+                let executor = executor(json.to_string());
+                let result = executor.execute(".[]");
+
+                assert!(result.is_ok());
+                let output = result.unwrap();
+                // Check that all three IDs appear in the output (format may vary)
+                assert!(output.contains("1"));
+                assert!(output.contains("2"));
+                assert!(output.contains("3"));
+                assert!(output.contains("id"));
+            }
+
+            #[test]
+            fn test_invalid_query_returns_error() {
+                let json = r#"{"name": "Dave"}"#;
+                let executor = executor(json.to_string());
+                let result = executor.execute(".invalid.[syntax");
+
+                assert!(result.is_err());
+                let error = result.unwrap_err();
+                assert!(!error.to_string().is_empty());
+            }
+
+            #[test]
+            fn test_nested_field_access() {
+                let json = r#"{"user": {"name": "Eve", "age": 28}}"#;
+                let executor = executor(json.to_string());
+                let result = executor.execute(".user.name");
+
+                assert!(result.is_ok());
+                let output = result.unwrap();
+                assert!(output.contains("Eve"));
+            }
+
+            #[test]
+            fn test_color_output_flag_present() {
+                // This test verifies that ANSI color codes are present in output
+                let json = r#"{"key": "value"}"#;
+                let executor = executor(json.to_string());
+                let result = executor.execute(".");
+
+                assert!(result.is_ok());
+                let output = result.unwrap();
+                // jq with --color-output produces ANSI escape codes
+                assert!(output.contains("\x1b[") || output.len() > json.len());
+            }
+
+            #[test]
+            fn test_cancelled_query_returns_error() {
+                let json = r#"{"key": "value"}"#;
+                let executor = executor(json.to_string());
+                let cancel = CancelToken::new();
+                cancel.cancel();
+
+                let result = executor.execute_cancellable("repeat(.)", &cancel);
+
+                assert_eq!(result, Err(QueryError::Cancelled));
+            }
+
+            #[test]
+            fn test_large_input_does_not_deadlock() {
+                // Larger than a pipe buffer in both directions
+                let json = format!("[{}]", vec!["\"some fairly long string value\""; 20_000].join(","));
+                let executor = executor(json);
+                let result = executor.execute(".[]");
+
+                assert!(result.is_ok());
+                assert_eq!(result.unwrap().lines().count(), 20_000);
+            }
+
+            #[test]
+            fn test_timeout_kills_runaway_query() {
+                let executor = executor("0".to_string()).with_limits(ExecutionLimits {
+                    timeout: Some(Duration::from_millis(200)),
+                    max_output_bytes: None,
+                });
+
+                let started = Instant::now();
+                let result = executor.execute("last(range(1e12))");
+
+                assert_eq!(result, Err(QueryError::TimedOut(Duration::from_millis(200))));
+                assert!(started.elapsed() < Duration::from_secs(5));
+            }
+
+            #[test]
+            fn test_output_limit_kills_runaway_query() {
+                let executor = executor("{}".to_string()).with_limits(ExecutionLimits {
+                    timeout: None,
+                    max_output_bytes: Some(64 * 1024),
+                });
+
+                let result = executor.execute("repeat(.)");
+
+                assert_eq!(result, Err(QueryError::OutputTooLarge(64 * 1024)));
+            }
+
+            #[test]
+            fn test_output_within_limit_succeeds() {
+                let json = r#"{"name": "Frank"}"#;
+                let executor = executor(json.to_string()).with_limits(ExecutionLimits {
+                    timeout: Some(Duration::from_secs(10)),
+                    max_output_bytes: Some(1024),
+                });
+
+                let result = executor.execute(".name");
+
+                assert!(result.unwrap().contains("Frank"));
+            }
+
+            #[test]
+            fn test_staged_pipeline_matches_whole_query() {
+                let json = r#"{"items": [{"name": "a", "n": 1.5}, {"name": "b", "n": -2}, {"name": "c\u001b", "n": 1e300}]}"#;
+                let queries = [
+                    ".items[] | select(.n > 0) | .name",
+                    ".items | map(.n) | add",
+                    ".items[] | {name} | tojson",
+                    ".items[] | .n as $n | $n * 2",
+                ];
+
+                let staged = executor(json).with_cache(1024 * 1024);
+                for query in queries {
+                    assert_eq!(staged.execute(query), executor(json).execute(query), "{}", query);
+                }
+            }
+
+            #[test]
+            fn test_staged_pipeline_keeps_non_finite_numbers() {
+                let json = r#"{"items": [{"n": 1.5}, {"n": -2}, {"n": 1e300}]}"#;
+                let queries = [
+                    "nan | isnan",
+                    "infinite | isinfinite",
+                    "[nan, 1] | map(type)",
+                    // Overflows to infinity, which a tail reading the printed prefix sees as finite
+                    ".items[] | .n * 1e300 * 1e300 | . > 1.7976931348623157e308",
+                ];
+
+                let staged = executor(json).with_cache(1024 * 1024);
+                for query in queries {
+                    assert_eq!(staged.execute(query), executor(json).execute(query), "{}", query);
+                }
+            }
+
+            #[test]
+            fn test_staged_pipeline_applies_input_mode_once() {
+                let options = JqOptions {
+                    input_mode: InputMode {
+                        slurp: true,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                let executor = executor("1 2 3").with_options(options).with_cache(1024);
+
+                let result = executor.execute(".[] | . * 10 | tostring").unwrap();
+
+                assert!(result.contains("\"10\""));
+                assert!(result.contains("\"30\""));
+            }
+
+            #[test]
+            fn test_named_args_are_forwarded() {
+                let options = JqOptions {
+                    named_args: vec![("env_name".into(), "prod".into())],
+                    named_json_args: vec![("limit".into(), "3".into())],
+                    ..Default::default()
+                };
+                let executor = executor("null".to_string()).with_options(options);
+
+                let result = executor.execute("[$env_name, $limit + 1]").unwrap();
+
+                assert!(result.contains("prod"));
+                assert!(result.contains('4'));
+            }
+
+            #[test]
+            fn test_positional_args_are_forwarded() {
+                let options = JqOptions {
+                    positional_args: vec!["first".into(), "second".into()],
+                    ..Default::default()
+                };
+                let executor = executor("null".to_string()).with_options(options);
+
+                let result = executor.execute("$ARGS.positional[1]").unwrap();
+
+                assert!(result.contains("second"));
+            }
+
+            #[test]
+            fn test_slurpfile_is_forwarded() {
+                let options = JqOptions {
+                    slurp_files: vec![("people".into(), "tests/fixtures/simple.json".into())],
+                    ..Default::default()
+                };
+                let executor = executor("null".to_string()).with_options(options);
+
+                let result = executor.execute("$people[0].name").unwrap();
+
+                assert!(result.contains("Alice"));
+            }
+        };
+    }
+
+    mod jq {
+        engine_tests!(JqProcess::default());
+    }
+
+    mod jaq {
+        engine_tests!(crate::query::jaq::Jaq::default().with_test_worker());
+    }
+
+    /// Engine that echoes the query and records each run with its input
+    /// and timeout; `sleep` takes 50ms
+    #[derive(Default)]
+    struct RecordingEngine {
+        runs: Mutex<Vec<(String, String)>>,
+        timeouts: Mutex<Vec<Option<Duration>>>,
+    }
+
+    impl RecordingEngine {
+        fn runs(&self) -> Vec<(String, String)> {
+            self.runs.lock().unwrap().clone()
+        }
+
+        fn timeouts(&self) -> Vec<Option<Duration>> {
+            self.timeouts.lock().unwrap().clone()
+        }
+    }
+
+    impl QueryEngine for RecordingEngine {
+        fn execute(
+            &self,
+            query: &str,
+            input: &InputData,
+            _options: &JqOptions,
+            limits: &ExecutionLimits,
+            _cancel: &CancelToken,
+        ) -> Result<String, QueryError> {
+            self.runs.lock().unwrap().push((query.to_string(), input.text().into_owned()));
+            self.timeouts.lock().unwrap().push(limits.timeout);
+            match query {
+                "sleep" => {
+                    std::thread::sleep(Duration::from_millis(50));
+                    Ok("sleep".to_string())
+                }
+                "cancel" => Err(QueryError::Cancelled),
+                "bad" => Err(QueryError::Jq("syntax error".to_string())),
+                "huge" => Err(QueryError::OutputTooLarge(1024)),
+                query => Ok(query.to_string()),
+            }
+        }
+
+        fn dialect(&self) -> super::super::engine::Dialect {
+            Default::default()
+        }
+    }
+
+    fn recording_executor(cache_budget: usize) -> (JqExecutor, Arc<RecordingEngine>) {
+        let engine = Arc::new(RecordingEngine::default());
+        let executor = JqExecutor::new("{}")
+            .with_engine(engine.clone())
+            .with_cache(cache_budget);
+        (executor, engine)
+    }
+
+    fn run(query: &str, input: &str) -> (String, String) {
+        (query.to_string(), input.to_string())
+    }
+
+    #[test]
+    fn test_repeated_query_is_served_from_cache() {
+        let (executor, engine) = recording_executor(1024);
+
+        assert_eq!(executor.execute(".a"), Ok(".a".to_string()));
+        assert_eq!(executor.execute(".b"), Ok(".b".to_string()));
+        assert_eq!(executor.execute(" .a "), Ok(".a".to_string()));
+        assert_eq!(executor.execute("bad"), Err(QueryError::Jq("syntax error".to_string())));
+        assert_eq!(executor.execute("bad"), Err(QueryError::Jq("syntax error".to_string())));
+
+        assert_eq!(engine.runs().len(), 3);
+    }
+
+    #[test]
+    fn test_queries_reading_argument_files_run_again() {
+        let engine = Arc::new(RecordingEngine::default());
+        let options = JqOptions {
+            raw_files: vec![("notes".into(), "notes.txt".into())],
+            ..Default::default()
+        };
+        let executor = JqExecutor::new("{}")
+            .with_engine(engine.clone())
+            .with_options(options)
+            .with_cache(1024);
+
+        for _ in 0..2 {
+            executor.execute("$notes").unwrap();
+            executor.execute("$notes_count").unwrap();
+        }
+
+        assert_eq!(engine.runs(), vec![run("$notes", "{}"), run("$notes_count", "{}"), run("$notes", "{}")]);
+    }
 
     #[test]
-    fn test_identity_filter() {
-        let json = r#"{"name": "Alice", "age": 30}"#;
-        let executor = JqExecutor::new(json.to_string());
-        let result = executor.execute(".");
+    fn test_cancelled_query_runs_again() {
+        let (executor, engine) = recording_executor(1024);
+
+        let _ = executor.execute("cancel");
+        let _ = executor.execute("cancel");
 
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        assert!(output.contains("Alice"));
-        assert!(output.contains("30"));
+        assert_eq!(engine.runs().len(), 2);
     }
 
     #[test]
-    fn test_empty_query_defaults_to_identity() {
-        let json = r#"{"name": "Bob"}"#;
-        let executor = JqExecutor::new(json.to_string());
-        let result = executor.execute("");
+    fn test_zero_budget_disables_cache() {
+        let (executor, engine) = recording_executor(0);
 
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        assert!(output.contains("Bob"));
+        executor.execute(".a").unwrap();
+        executor.execute(".a").unwrap();
+
+        assert_eq!(engine.runs().len(), 2);
     }
 
     #[test]
-    fn test_field_selection() {
-        let json = r#"{"name": "Charlie", "age": 25, "city": "NYC"}"#;
-        let executor = JqExecutor::new(json.to_string());
-        let result = executor.execute(".name");
+    fn test_pipeline_tail_runs_against_cached_prefix() {
+        let (executor, engine) = recording_executor(1024);
+
+        assert_eq!(executor.execute(".a | .b | .c"), Ok(".c".to_string()));
+        assert_eq!(executor.execute(".a | .b | .d"), Ok(".d".to_string()));
+        assert_eq!(executor.execute(".a|.e"), Ok(".e".to_string()));
 
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        assert!(output.contains("Charlie"));
-        assert!(!output.contains("NYC"));
+        assert_eq!(
+            engine.runs(),
+            vec![
+                run(".a", "{}"),
+                run(".b", ".a"),
+                run(".c", ".b"),
+                run(".d", ".b"),
+                run(".e", ".a"),
+            ]
+        );
     }
 
     #[test]
-    fn test_array_iteration() {
-        let json = r#"[{"id": 1}, {"id": 2}, {"id": 3}]"#;
-        let executor = JqExecutor::new(json.to_string());
-        let result = executor.execute(".[]");
+    fn test_pipeline_runs_whole_without_cache() {
+        let (executor, engine) = recording_executor(0);
+
+        executor.execute(".a | .b").unwrap();
 
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        // Check that all three IDs appear in the output (format may vary)
-        assert!(output.contains("1"));
-        assert!(output.contains("2"));
-        assert!(output.contains("3"));
-        assert!(output.contains("id"));
+        assert_eq!(engine.runs(), vec![run(".a | .b", "{}")]);
     }
 
     #[test]
-    fn test_invalid_query_returns_error() {
-        let json = r#"{"name": "Dave"}"#;
-        let executor = JqExecutor::new(json.to_string());
-        let result = executor.execute(".invalid.[syntax");
+    fn test_failing_prefix_fails_pipeline() {
+        let (executor, engine) = recording_executor(1024);
 
-        assert!(result.is_err());
-        let error = result.unwrap_err();
-        assert!(!error.is_empty());
+        assert_eq!(executor.execute("bad | .b"), Err(QueryError::Jq("syntax error".to_string())));
+        assert_eq!(executor.execute("cancel | .b"), Err(QueryError::Cancelled));
+
+        assert_eq!(engine.runs(), vec![run("bad", "{}"), run("cancel", "{}")]);
     }
 
     #[test]
-    fn test_nested_field_access() {
-        let json = r#"{"user": {"name": "Eve", "age": 28}}"#;
-        let executor = JqExecutor::new(json.to_string());
-        let result = executor.execute(".user.name");
+    fn test_large_prefix_output_runs_whole_pipeline() {
+        let (executor, engine) = recording_executor(1024);
+
+        executor.execute("huge | .b").unwrap();
+
+        assert_eq!(engine.runs(), vec![run("huge", "{}"), run("huge | .b", "{}")]);
+    }
+
+    #[test]
+    fn test_pipeline_stages_share_timeout() {
+        let (executor, engine) = recording_executor(1024);
+        let executor = executor.with_limits(ExecutionLimits {
+            timeout: Some(Duration::from_secs(10)),
+            max_output_bytes: None,
+        });
+
+        executor.execute("sleep | .b").unwrap();
+
+        let timeouts = engine.timeouts();
+        assert_eq!(timeouts.len(), 2);
+        assert!(timeouts[1].unwrap() <= Duration::from_secs(10) - Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_tail_past_deadline_times_out() {
+        let (executor, engine) = recording_executor(1024);
+        let executor = executor.with_limits(ExecutionLimits {
+            timeout: Some(Duration::from_millis(20)),
+            max_output_bytes: None,
+        });
+
+        // The engine doesn't stop `sleep`, but the tail has no time left
+        let result = executor.execute("sleep | .b");
 
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        assert!(output.contains("Eve"));
+        assert_eq!(result, Err(QueryError::TimedOut(Duration::from_millis(20))));
+        assert_eq!(engine.runs(), vec![run("sleep", "{}")]);
     }
 
     #[test]
-    fn test_color_output_flag_present() {
-        // This test verifies that ANSI color codes are present in output
-        let json = r#"{"key": "value"}"#;
-        let executor = JqExecutor::new(json.to_string());
-        let result = executor.execute(".");
-
-        assert!(result.is_ok());
-        let output = result.unwrap();
-        // jq with --color-output produces ANSI escape codes
-        assert!(output.contains("\x1b[") || output.len() > json.len());
+    fn test_split_last_stage() {
+        assert_eq!(split_last_stage(".a | .b | .c"), Some((".a | .b", ".c")));
+        assert_eq!(split_last_stage(".a"), None);
+        assert_eq!(split_last_stage(".a |"), None);
+        assert_eq!(split_last_stage("| .a"), None);
+        assert_eq!(split_last_stage(".a | [inputs]"), None);
+        assert_eq!(split_last_stage(".a | input_filename"), None);
+        assert_eq!(split_last_stage(".inputs_seen | .b"), Some((".inputs_seen", ".b")));
+        assert_eq!(split_last_stage("[nan] | .[0] | isnan"), None);
+        assert_eq!(split_last_stage(".a | log"), None);
+        assert_eq!(split_last_stage(".nan_count | .b"), Some((".nan_count", ".b")));
+    }
+
+    #[test]
+    fn test_engines_give_same_output() {
+        let json = r#"{"users": [{"name": "Zoë", "age": 30.0, "tags": []}, {"name": "Bob\t\"B\"", "age": 1e-7}], "meta": {}, "big": 1e300}"#;
+        let queries = [
+            ".",
+            ".users[]",
+            ".users | map(.age * 3)",
+            "[.users[].name | ascii_downcase]",
+            "keys",
+            ".users[0] | to_entries",
+            ".big, null, true, -1",
+            ".users | length",
+        ];
+
+        for query in queries {
+            let jq = JqExecutor::new(json).execute(query);
+            let jaq = JqExecutor::new(json).with_engine(Arc::new(Jaq::default())).execute(query);
+            assert_eq!(jq, jaq, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_engines_give_same_output_with_output_options() {
+        let json = r#"{"b": [1, {"y": "z", "x": null}], "a": "text", "e": [], "o": {}}"#;
+        let layouts = [
+            (true, false, false, false, false),
+            (false, true, false, false, false),
+            (false, false, true, false, false),
+            (false, false, false, true, false),
+            (false, false, false, false, true),
+            (true, true, false, true, true),
+        ];
+
+        for (raw, compact, tab, sort_keys, monochrome) in layouts {
+            let options = JqOptions {
+                output: OutputOptions {
+                    raw,
+                    compact,
+                    tab,
+                    sort_keys,
+                    monochrome,
+                    format: OutputFormat::Json,
+                },
+                ..Default::default()
+            };
+            for query in [".", ".a, .b, .e, .o"] {
+                let jq = JqExecutor::new(json).with_options(options.clone()).execute(query);
+                let jaq = JqExecutor::new(json)
+                    .with_engine(Arc::new(Jaq::default()))
+                    .with_options(options.clone())
+                    .execute(query);
+                assert_eq!(jq, jaq, "{} {:?}", query, options.output);
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_format_is_converted() {
+        let json = r#"[{"name": "Alice", "age": 30}, {"name": "Bob", "age": 25}]"#;
+        let options = JqOptions {
+            output: OutputOptions {
+                format: OutputFormat::Csv,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let executor = JqExecutor::new(json).with_engine(Arc::new(Jaq::default())).with_options(options);
+
+        assert_eq!(executor.execute("."), Ok("name,age\nAlice,30\nBob,25\n".to_string()));
+        assert!(matches!(executor.execute("{a: [1]}, [2]"), Err(QueryError::Output(_))));
+    }
+
+    #[test]
+    fn test_pipeline_runs_whole_when_output_is_not_json() {
+        let engine = Arc::new(RecordingEngine::default());
+        let options = JqOptions {
+            output: OutputOptions {
+                raw: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let executor = JqExecutor::new("{}")
+            .with_engine(engine.clone())
+            .with_options(options)
+            .with_cache(1024);
+
+        executor.execute(".a | .b").unwrap();
+
+        assert_eq!(engine.runs(), vec![run(".a | .b", "{}")]);
     }
 }
@@ -0,0 +1,767 @@
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::iter;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::Instant;
+
+use jaq_core::load::{self, Arena, File, Loader};
+use jaq_core::box_iter::box_once;
+use jaq_core::{compile, Compiler, Ctx, Error, Exn, Native, RcIter};
+use jaq_json::Val;
+use serde_json::{Map, Value};
+
+use super::engine::{Dialect, QueryEngine};
+use super::executor::{CancelToken, ExecutionLimits};
+use super::jq_process;
+use super::options::JqOptions;
+use super::output::OutputOptions;
+use crate::error::QueryError;
+use crate::input::data::InputData;
+use crate::input::mode::InputMode;
+
+/// First argument that makes jiq evaluate one query with jaq and exit, like a
+/// jq process (see `run_worker`)
+pub const WORKER_FLAG: &str = "--jaq-worker";
+
+/// Stack size of the evaluation thread (jq filters recurse deeply)
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Built-ins that fail instead, because they would exit jiq or write over the TUI
+const DISABLED_FUNCTIONS: &[&str] = &["halt", "halt_error", "stderr"];
+
+const DISABLED_MESSAGE: &str = "halt, halt_error and stderr are not available in jiq";
+
+/// jq's colors for `--color-output`
+const NULL_COLOR: &str = "\x1b[1;30m";
+const SCALAR_COLOR: &str = "\x1b[0;39m";
+const STRING_COLOR: &str = "\x1b[0;32m";
+const CONTAINER_COLOR: &str = "\x1b[1;39m";
+const FIELD_COLOR: &str = "\x1b[34;1m";
+const RESET: &str = "\x1b[0m";
+
+/// Runs queries with jaq, a jq clone written in Rust
+///
+/// Output is formatted exactly like jq's, colored unless monochrome output
+/// is requested. With a worker program each query runs in a new process
+/// that is killed when cancelled or over a limit, exactly like jq. Without
+/// one, queries are evaluated in-process and limits are only checked between
+/// output values, so a filter like `[repeat(1)]` runs until memory runs out.
+#[derive(Default)]
+pub struct Jaq {
+    /// Program run as `PROGRAM --jaq-worker JQ-ARGS...` (jiq itself)
+    worker: Option<PathBuf>,
+}
+
+impl Jaq {
+    /// Evaluate each query in a new `program` process (see `WORKER_FLAG`)
+    pub fn with_worker(mut self, program: impl Into<PathBuf>) -> Self {
+        self.worker = Some(program.into());
+        self
+    }
+
+    /// Evaluate in jiq's own binary, which cargo builds next to the test binary
+    #[cfg(test)]
+    pub fn with_test_worker(self) -> Self {
+        let program = env::current_exe()
+            .ok()
+            .and_then(|exe| Some(exe.parent()?.parent()?.join(format!("jiq{}", env::consts::EXE_SUFFIX))))
+            .expect("test binary is in target/");
+        assert!(program.is_file(), "{} isn't built", program.display());
+        self.with_worker(program)
+    }
+}
+
+impl QueryEngine for Jaq {
+    fn dialect(&self) -> Dialect {
+        Dialect::Jaq
+    }
+
+    fn execute(
+        &self,
+        query: &str,
+        input: &InputData,
+        options: &JqOptions,
+        limits: &ExecutionLimits,
+        cancel: &CancelToken,
+    ) -> Result<String, QueryError> {
+        if let Some(program) = &self.worker {
+            let mut command = Command::new(program);
+            command.arg(WORKER_FLAG).args(options.command_args(query));
+            return jq_process::run(command, input, limits, cancel);
+        }
+
+        let started = Instant::now();
+        evaluate_on_stack(|| {
+            evaluate(query, &input.text(), options, |output| check_limits(output, limits, cancel, started))
+        })
+    }
+}
+
+/// The worker process: evaluate the query given by jq's arguments (from
+/// `JqOptions::command_args`) on stdin and print each value like jq, with no
+/// limits (the parent kills the process instead)
+pub fn run_worker(args: impl IntoIterator<Item = String>) -> Result<(), QueryError> {
+    let (query, options) = JqOptions::from_command_args(args)
+        .map_err(|e| QueryError::Process(format!("jaq worker: {}", e)))?;
+
+    let mut input = Vec::new();
+    io::stdin()
+        .read_to_end(&mut input)
+        .map_err(|e| QueryError::Process(format!("jaq worker: failed to read stdin: {}", e)))?;
+    let text = String::from_utf8_lossy(&input);
+
+    evaluate_on_stack(|| {
+        let write_error = |e: io::Error| QueryError::Process(format!("jaq worker: {}", e));
+        let mut stdout = BufWriter::new(io::stdout());
+        // Streamed, so the parent sees the output grow past its limit
+        let rest = evaluate(&query, &text, &options, |output| {
+            stdout.write_all(output.as_bytes()).map_err(write_error)?;
+            output.clear();
+            Ok(())
+        })?;
+        stdout.write_all(rest.as_bytes()).and_then(|_| stdout.flush()).map_err(write_error)
+    })
+}
+
+/// Run `evaluate` on a thread with a stack deep enough for recursive filters
+fn evaluate_on_stack<T: Send>(
+    evaluate: impl FnOnce() -> Result<T, QueryError> + Send,
+) -> Result<T, QueryError> {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .name("jaq".to_string())
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, evaluate)
+            .map_err(|e| QueryError::Process(format!("Failed to start jaq: {}", e)))?
+            .join()
+            .map_err(|_| QueryError::Process("jaq stopped unexpectedly".to_string()))?
+    })
+}
+
+/// Evaluate `query` on the input `text`, formatted like jq's output, calling
+/// `after_value` with the output so far after each value (it may stop the
+/// query, or take the output)
+fn evaluate(
+    query: &str,
+    text: &str,
+    options: &JqOptions,
+    mut after_value: impl FnMut(&mut String) -> Result<(), QueryError>,
+) -> Result<String, QueryError> {
+    let (names, values) = global_variables(options)?;
+
+    let arena = Arena::default();
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let modules = loader
+        .load(&arena, File { code: query, path: () })
+        .map_err(|errors| load_error(query, errors))?;
+
+    let functions = jaq_std::funs().chain(jaq_json::funs()).map(|(name, args, native)| {
+        if DISABLED_FUNCTIONS.contains(&name) {
+            (name, args, Native::new(|_, _| box_once(Err(Exn::from(Error::str(DISABLED_MESSAGE))))))
+        } else {
+            (name, args, native)
+        }
+    });
+    let filter = Compiler::default()
+        .with_funs(functions)
+        .with_global_vars(names.iter().map(String::as_str))
+        .compile(modules)
+        .map_err(|errors| compile_error(query, errors))?;
+
+    let mode = options.input_mode;
+    let inputs = RcIter::new(input_values(text, mode));
+    let roots: Box<dyn Iterator<Item = Result<Val, String>>> = if mode.null_input {
+        Box::new(iter::once(Ok(Val::Null)))
+    } else {
+        Box::new(&inputs)
+    };
+
+    let layout = Layout::new(&options.output);
+    let mut output = String::new();
+    let mut errors = String::new();
+    for root in roots {
+        let root = match root {
+            Ok(root) => root,
+            Err(e) => {
+                errors.push_str(&format!("jaq: error: {}\n", e));
+                break;
+            }
+        };
+
+        // Like jq, an error ends the current input but not the ones after it
+        for result in filter.run((Ctx::new(values.iter().cloned(), &inputs), root)) {
+            match result {
+                Ok(value) => {
+                    match value {
+                        // jq prints raw strings uncolored
+                        Val::Str(text) if options.output.raw => output.push_str(&text),
+                        value => write_value(&mut output, &value, &layout, 0),
+                    }
+                    output.push('\n');
+                }
+                Err(e) => {
+                    errors.push_str(&format!("jaq: error: {}\n", e));
+                    break;
+                }
+            }
+            after_value(&mut output)?;
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(output)
+    } else {
+        Err(QueryError::Jq(errors))
+    }
+}
+
+/// Stop between output values once cancelled or over a limit
+fn check_limits(
+    output: &str,
+    limits: &ExecutionLimits,
+    cancel: &CancelToken,
+    started: Instant,
+) -> Result<(), QueryError> {
+    if cancel.is_cancelled() {
+        return Err(QueryError::Cancelled);
+    }
+    if let Some(max) = limits.max_output_bytes
+        && output.len() > max
+    {
+        return Err(QueryError::OutputTooLarge(max));
+    }
+    if let Some(timeout) = limits.timeout
+        && started.elapsed() >= timeout
+    {
+        return Err(QueryError::TimedOut(timeout));
+    }
+    Ok(())
+}
+
+/// Names (with `$`) and values of the variables defined by the jq options,
+/// plus `$ENV`
+fn global_variables(options: &JqOptions) -> Result<(Vec<String>, Vec<Val>), QueryError> {
+    let mut variables: Vec<(String, Value)> = Vec::new();
+    let mut named = Map::new();
+
+    for (name, value) in &options.named_args {
+        named.insert(name.clone(), Value::String(value.clone()));
+        variables.push((name.clone(), Value::String(value.clone())));
+    }
+    for (name, text) in &options.named_json_args {
+        let value = parse_argument(&format!("--argjson {}", name), text)?;
+        named.insert(name.clone(), value.clone());
+        variables.push((name.clone(), value));
+    }
+    for (name, path) in &options.slurp_files {
+        let text = read_argument_file(name, path)?;
+        let documents = serde_json::Deserializer::from_str(&text)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| argument_error(&format!("--slurpfile {}", name), e))?;
+        variables.push((name.clone(), Value::Array(documents)));
+    }
+    for (name, path) in &options.raw_files {
+        variables.push((name.clone(), Value::String(read_argument_file(name, path)?)));
+    }
+
+    let mut positional: Vec<Value> = options.positional_args.iter().cloned().map(Value::String).collect();
+    for text in &options.positional_json_args {
+        positional.push(parse_argument("--jsonargs", text)?);
+    }
+
+    let mut args = Map::new();
+    args.insert("positional".to_string(), Value::Array(positional));
+    args.insert("named".to_string(), Value::Object(named));
+    variables.push(("ARGS".to_string(), Value::Object(args)));
+    variables.push((
+        "ENV".to_string(),
+        Value::Object(env::vars().map(|(key, value)| (key, Value::String(value))).collect()),
+    ));
+
+    Ok(variables
+        .into_iter()
+        .map(|(name, value)| (format!("${}", name), Val::from(value)))
+        .unzip())
+}
+
+fn parse_argument(flag: &str, text: &str) -> Result<Value, QueryError> {
+    serde_json::from_str(text).map_err(|e| argument_error(flag, e))
+}
+
+fn read_argument_file(name: &str, path: &std::path::Path) -> Result<String, QueryError> {
+    fs::read_to_string(path).map_err(|e| argument_error(&format!("{}: {}", name, path.display()), e))
+}
+
+fn argument_error(context: &str, error: impl std::fmt::Display) -> QueryError {
+    QueryError::Jq(format!("jaq: error: {}: {}\n", context, error))
+}
+
+/// The values jq reads from its input, depending on `--raw-input` and `--slurp`
+fn input_values(text: &str, mode: InputMode) -> Box<dyn Iterator<Item = Result<Val, String>> + '_> {
+    if mode.raw_input {
+        if mode.slurp {
+            return Box::new(iter::once(Ok(Val::from(text.to_string()))));
+        }
+        // Like jq, a final newline doesn't start another (empty) line
+        let lines = text.strip_suffix('\n').unwrap_or(text);
+        return Box::new(
+            (!text.is_empty())
+                .then(|| lines.split('\n'))
+                .into_iter()
+                .flatten()
+                .map(|line| Ok(Val::from(line.to_string()))),
+        );
+    }
+
+    let documents = serde_json::Deserializer::from_str(text)
+        .into_iter::<Value>()
+        .map(|document| document.map(Val::from).map_err(|e| e.to_string()));
+    if mode.slurp {
+        Box::new(iter::once(documents.collect::<Result<Val, String>>()))
+    } else {
+        Box::new(documents)
+    }
+}
+
+fn load_error(query: &str, errors: load::Errors<&str, ()>) -> QueryError {
+    let mut message = String::new();
+    for (_, error) in errors {
+        let problems: Vec<String> = match error {
+            load::Error::Io(errors) => errors
+                .into_iter()
+                .map(|(path, e)| format!("cannot load {}: {}", path, e))
+                .collect(),
+            load::Error::Lex(errors) => errors
+                .into_iter()
+                .map(|(expected, found)| format!("expected {}{}", expected.as_str(), position(query, found)))
+                .collect(),
+            load::Error::Parse(errors) => errors
+                .into_iter()
+                .map(|(expected, found)| format!("expected {}{}", expected.as_str(), position(query, found)))
+                .collect(),
+        };
+        for problem in problems {
+            let _ = writeln!(message, "jaq: error: {}", problem);
+        }
+    }
+    QueryError::Jq(message)
+}
+
+fn compile_error(query: &str, errors: compile::Errors<&str, ()>) -> QueryError {
+    let mut message = String::new();
+    for (name, undefined) in errors.into_iter().flat_map(|(_, errors)| errors) {
+        let _ = writeln!(
+            message,
+            "jaq: error: undefined {} `{}`{}",
+            undefined.as_str(),
+            name,
+            position(query, name)
+        );
+    }
+    QueryError::Jq(message)
+}
+
+/// " at column N" for a slice of the query text
+fn position(query: &str, span: &str) -> String {
+    let start = query.as_ptr() as usize;
+    let offset = (span.as_ptr() as usize).wrapping_sub(start);
+    if offset > query.len() || !query.is_char_boundary(offset) {
+        return String::new();
+    }
+    if offset == query.len() {
+        return " at end of query".to_string();
+    }
+    format!(" at column {}", query[..offset].chars().count() + 1)
+}
+
+/// How values are laid out (`--compact-output`, `--tab`, `--sort-keys`, `--monochrome-output`)
+struct Layout {
+    /// Indentation per level, `None` for everything on one line
+    indent: Option<&'static str>,
+    sort_keys: bool,
+    color: bool,
+}
+
+impl Layout {
+    fn new(output: &OutputOptions) -> Self {
+        let indent = if output.compact {
+            None
+        } else if output.tab {
+            Some("\t")
+        } else {
+            Some("  ")
+        };
+        Self {
+            indent,
+            sort_keys: output.sort_keys,
+            color: !output.monochrome,
+        }
+    }
+}
+
+/// Append `value` the way jq prints it
+fn write_value(output: &mut String, value: &Val, layout: &Layout, depth: usize) {
+    match value {
+        Val::Null => write_colored(output, layout, NULL_COLOR, "null"),
+        Val::Bool(true) => write_colored(output, layout, SCALAR_COLOR, "true"),
+        Val::Bool(false) => write_colored(output, layout, SCALAR_COLOR, "false"),
+        // jq stores every number as a double
+        Val::Int(number) => write_colored(output, layout, SCALAR_COLOR, &format_number(*number as f64)),
+        Val::Float(number) => write_colored(output, layout, SCALAR_COLOR, &format_number(*number)),
+        Val::Num(number) => {
+            let number = number.parse().unwrap_or(f64::NAN);
+            write_colored(output, layout, SCALAR_COLOR, &format_number(number));
+        }
+        Val::Str(text) => {
+            paint(output, layout, STRING_COLOR);
+            write_string(output, text);
+            paint(output, layout, RESET);
+        }
+        Val::Arr(items) if items.is_empty() => write_colored(output, layout, CONTAINER_COLOR, "[]"),
+        Val::Arr(items) => {
+            paint(output, layout, CONTAINER_COLOR);
+            output.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_newline(output, layout, depth + 1);
+                write_value(output, item, layout, depth + 1);
+                paint(output, layout, CONTAINER_COLOR);
+            }
+            write_newline(output, layout, depth);
+            write_colored(output, layout, CONTAINER_COLOR, "]");
+        }
+        Val::Obj(fields) if fields.is_empty() => write_colored(output, layout, CONTAINER_COLOR, "{}"),
+        Val::Obj(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            if layout.sort_keys {
+                fields.sort_by_key(|(key, _)| *key);
+            }
+            let separator = if layout.indent.is_some() { ": " } else { ":" };
+
+            paint(output, layout, CONTAINER_COLOR);
+            output.push('{');
+            for (index, (key, value)) in fields.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_newline(output, layout, depth + 1);
+                paint(output, layout, RESET);
+                paint(output, layout, FIELD_COLOR);
+                write_string(output, key);
+                paint(output, layout, RESET);
+                write_colored(output, layout, CONTAINER_COLOR, separator);
+                write_value(output, value, layout, depth + 1);
+                paint(output, layout, CONTAINER_COLOR);
+            }
+            write_newline(output, layout, depth);
+            write_colored(output, layout, CONTAINER_COLOR, "}");
+        }
+    }
+}
+
+fn write_colored(output: &mut String, layout: &Layout, color: &str, text: &str) {
+    paint(output, layout, color);
+    output.push_str(text);
+    paint(output, layout, RESET);
+}
+
+/// Switch to `color`, unless the output is monochrome
+fn paint(output: &mut String, layout: &Layout, color: &str) {
+    if layout.color {
+        output.push_str(color);
+    }
+}
+
+/// Start a line at `depth`; nothing in compact output
+fn write_newline(output: &mut String, layout: &Layout, depth: usize) {
+    if let Some(indent) = layout.indent {
+        output.push('\n');
+        output.extend(iter::repeat_n(indent, depth));
+    }
+}
+
+/// A JSON string literal with jq's escaping
+fn write_string(output: &mut String, text: &str) {
+    output.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            '\u{8}' => output.push_str("\\b"),
+            '\u{c}' => output.push_str("\\f"),
+            c if c < ' ' || c == '\u{7f}' => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+/// Format a number like jq: the shortest digits that round-trip, in
+/// exponent notation when very large or small
+fn format_number(number: f64) -> String {
+    if number.is_nan() {
+        return "null".to_string();
+    }
+    // jq prints infinities as the largest finite double
+    let number = number.clamp(-f64::MAX, f64::MAX);
+    if number == 0.0 {
+        return if number.is_sign_negative() { "-0" } else { "0" }.to_string();
+    }
+
+    let scientific = format!("{:e}", number.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("`{:e}` has an exponent");
+    let digits = mantissa.replace('.', "");
+    let digit_count = digits.len() as i32;
+    // Position of the decimal point relative to the start of `digits`
+    let point = exponent.parse::<i32>().expect("`{:e}` exponent is an integer") + 1;
+
+    let mut formatted = if number < 0.0 { "-".to_string() } else { String::new() };
+    if point <= -4 || point > digit_count + 15 {
+        formatted.push_str(&digits[..1]);
+        if digit_count > 1 {
+            formatted.push('.');
+            formatted.push_str(&digits[1..]);
+        }
+        let exponent = point - 1;
+        let sign = if exponent < 0 { '-' } else { '+' };
+        let _ = write!(formatted, "e{}{:02}", sign, exponent.abs());
+    } else if point <= 0 {
+        formatted.push_str("0.");
+        formatted.extend(iter::repeat_n('0', (-point) as usize));
+        formatted.push_str(&digits);
+    } else if point >= digit_count {
+        formatted.push_str(&digits);
+        formatted.extend(iter::repeat_n('0', (point - digit_count) as usize));
+    } else {
+        formatted.push_str(&digits[..point as usize]);
+        formatted.push('.');
+        formatted.push_str(&digits[point as usize..]);
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn run(query: &str, input: &str) -> Result<String, QueryError> {
+        Jaq::default().execute(
+            query,
+            &InputData::from(input),
+            &JqOptions::default(),
+            &ExecutionLimits::default(),
+            &CancelToken::new(),
+        )
+    }
+
+    #[test]
+    fn test_number_formatting_matches_jq() {
+        let cases = [
+            (1.0, "1"),
+            (2.5, "2.5"),
+            (-0.0, "-0"),
+            (0.1, "0.1"),
+            (0.0001, "0.0001"),
+            (1e-5, "1e-05"),
+            (3e-7, "3e-07"),
+            (1e15, "1000000000000000"),
+            (1e16, "1e+16"),
+            (1e17, "1e+17"),
+            (123456789012345678.0, "123456789012345680"),
+            (1e100, "1e+100"),
+            (f64::INFINITY, "1.7976931348623157e+308"),
+            (f64::NAN, "null"),
+        ];
+        for (number, expected) in cases {
+            assert_eq!(format_number(number), expected, "{}", number);
+        }
+    }
+
+    #[test]
+    fn test_string_escaping_matches_jq() {
+        let mut output = String::new();
+        write_string(&mut output, "q\"b\\n\n\t\u{1}\u{7f}é");
+        assert_eq!(output, r#""q\"b\\n\n\t\u0001\u007fé""#);
+    }
+
+    #[test]
+    fn test_colored_layout_matches_jq() {
+        let output = run(".", r#"{"a": [1, null], "b": {}}"#).unwrap();
+        assert_eq!(
+            output,
+            "\x1b[1;39m{\n  \
+             \x1b[0m\x1b[34;1m\"a\"\x1b[0m\x1b[1;39m: \x1b[0m\x1b[1;39m[\n    \
+             \x1b[0;39m1\x1b[0m\x1b[1;39m,\n    \
+             \x1b[1;30mnull\x1b[0m\x1b[1;39m\n  \
+             \x1b[1;39m]\x1b[0m\x1b[1;39m,\n  \
+             \x1b[0m\x1b[34;1m\"b\"\x1b[0m\x1b[1;39m: \x1b[0m\x1b[1;39m{}\x1b[0m\x1b[1;39m\n\
+             \x1b[1;39m}\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_output_options_match_jq() {
+        let run_with = |output: OutputOptions| {
+            let options = JqOptions {
+                output,
+                ..Default::default()
+            };
+            Jaq::default().execute(
+                ".b, .a",
+                &InputData::from(r#"{"a": "s", "b": {"y": [1], "x": null}}"#),
+                &options,
+                &ExecutionLimits::default(),
+                &CancelToken::new(),
+            )
+            .unwrap()
+        };
+
+        let compact = run_with(OutputOptions {
+            compact: true,
+            raw: true,
+            sort_keys: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            compact,
+            "\x1b[1;39m{\x1b[0m\x1b[34;1m\"x\"\x1b[0m\x1b[1;39m:\x1b[0m\x1b[1;30mnull\x1b[0m\x1b[1;39m,\
+             \x1b[0m\x1b[34;1m\"y\"\x1b[0m\x1b[1;39m:\x1b[0m\x1b[1;39m[\x1b[0;39m1\x1b[0m\x1b[1;39m\x1b[1;39m]\x1b[0m\
+             \x1b[1;39m\x1b[1;39m}\x1b[0m\ns\n"
+        );
+
+        let tab = run_with(OutputOptions {
+            tab: true,
+            ..Default::default()
+        });
+        assert!(tab.starts_with("\x1b[1;39m{\n\t\x1b[0m\x1b[34;1m\"y\""), "{:?}", tab);
+        assert!(tab.contains("[\n\t\t\x1b[0;39m1"), "{:?}", tab);
+    }
+
+    #[test]
+    fn test_runtime_error_is_reported() {
+        let error = run(".a", "{\"a\": 1} 2").unwrap_err();
+        assert!(matches!(error, QueryError::Jq(ref message) if message.starts_with("jaq: error:")), "{:?}", error);
+    }
+
+    #[test]
+    fn test_syntax_error_reports_column() {
+        let error = run(".a | (", "{}").unwrap_err().to_string();
+        assert!(error.contains("at end of query"), "{}", error);
+
+        let error = run(".a | nosuchfn", "{}").unwrap_err().to_string();
+        assert!(error.contains("undefined filter `nosuchfn` at column 6"), "{}", error);
+    }
+
+    #[test]
+    fn test_process_exiting_builtins_are_unavailable() {
+        for query in ["halt", "halt_error", "\"x\" | stderr"] {
+            let error = run(query, "null").unwrap_err().to_string();
+            assert!(error.contains(DISABLED_MESSAGE), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_raw_input_lines() {
+        let options = JqOptions {
+            input_mode: InputMode { raw_input: true, ..Default::default() },
+            ..Default::default()
+        };
+        let output = Jaq::default()
+            .execute(
+                "length",
+                &InputData::from("ab\n\ncde\n"),
+                &options,
+                &ExecutionLimits::default(),
+                &CancelToken::new(),
+            )
+            .unwrap();
+        assert_eq!(output, "\x1b[0;39m2\x1b[0m\n\x1b[0;39m0\x1b[0m\n\x1b[0;39m3\x1b[0m\n");
+    }
+
+    #[test]
+    fn test_range_matches_jq() {
+        let options = JqOptions {
+            output: OutputOptions { monochrome: true, compact: true, ..Default::default() },
+            ..Default::default()
+        };
+        let query = "[range(5)], [range(0; 1; 0.25)], [range(3; 0; -1)], [range(1; 1)], [limit(2; range(0; 1; 0))]";
+        let output = Jaq::default()
+            .execute(query, &InputData::from("null"), &options, &ExecutionLimits::default(), &CancelToken::new())
+            .unwrap();
+        assert_eq!(output, "[0,1,2,3,4]\n[0,0.25,0.5,0.75]\n[3,2,1]\n[]\n[0,0]\n");
+    }
+
+    #[test]
+    fn test_worker_matches_in_process_evaluation() {
+        let options = JqOptions {
+            named_args: vec![("who".into(), "x".into())],
+            ..Default::default()
+        };
+        let input = InputData::from(r#"{"a": [1, "s"]} 2"#);
+        let run_with = |engine: Jaq, query: &str| {
+            engine.execute(query, &input, &options, &ExecutionLimits::default(), &CancelToken::new())
+        };
+
+        for query in [".", "[$who, .a?]", ".a | length"] {
+            let expected = run_with(Jaq::default(), query);
+            assert_eq!(run_with(Jaq::default().with_test_worker(), query), expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_worker_is_killed_at_the_limits() {
+        let engine = Jaq::default().with_test_worker();
+        let input = InputData::from("null");
+        let timeout = Duration::from_millis(200);
+        let limits = ExecutionLimits {
+            timeout: Some(timeout),
+            max_output_bytes: None,
+        };
+
+        // Never produces a value, so only killing the process stops it
+        let result = engine.execute("[repeat(1)]", &input, &JqOptions::default(), &limits, &CancelToken::new());
+        assert_eq!(result, Err(QueryError::TimedOut(timeout)));
+
+        let limits = ExecutionLimits {
+            timeout: None,
+            max_output_bytes: Some(1000),
+        };
+        let result = engine.execute("repeat(1)", &input, &JqOptions::default(), &limits, &CancelToken::new());
+        assert_eq!(result, Err(QueryError::OutputTooLarge(1000)));
+
+        // The next query runs as usual
+        let result = engine.execute("1", &input, &JqOptions::default(), &limits, &CancelToken::new());
+        assert_eq!(result, Ok("\x1b[0;39m1\x1b[0m\n".to_string()));
+    }
+
+    #[test]
+    fn test_null_input_reads_with_inputs() {
+        let options = JqOptions {
+            input_mode: InputMode { null_input: true, ..Default::default() },
+            ..Default::default()
+        };
+        let output = Jaq::default()
+            .execute(
+                "[inputs] | length",
+                &InputData::from("1 2 3"),
+                &options,
+                &ExecutionLimits::default(),
+                &CancelToken::new(),
+            )
+            .unwrap();
+        assert_eq!(output, "\x1b[0;39m3\x1b[0m\n");
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, ScopedJoinHandle};
+use std::time::Instant;
+
+use super::engine::{Dialect, QueryEngine};
+use super::executor::{CancelToken, ExecutionLimits, WAIT_POLL_INTERVAL};
+use super::options::JqOptions;
+use super::version::JqVersion;
+use crate::error::QueryError;
+use crate::input::data::InputData;
+
+/// Runs each query in a new `jq` process, feeding the input on stdin
+///
+/// Limits and cancellation are enforced by killing the process.
+pub struct JqProcess {
+    /// The jq binary, a name looked up in PATH or a path
+    program: PathBuf,
+    version: Option<JqVersion>,
+}
+
+impl JqProcess {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            version: None,
+        }
+    }
+
+    /// Record the detected jq release, which decides the builtins offered
+    pub fn with_version(mut self, version: Option<JqVersion>) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+/// `jq` from PATH
+impl Default for JqProcess {
+    fn default() -> Self {
+        Self::new("jq")
+    }
+}
+
+impl QueryEngine for JqProcess {
+    fn dialect(&self) -> Dialect {
+        Dialect::Jq(self.version)
+    }
+
+    fn execute(
+        &self,
+        query: &str,
+        input: &InputData,
+        options: &JqOptions,
+        limits: &ExecutionLimits,
+        cancel: &CancelToken,
+    ) -> Result<String, QueryError> {
+        let mut command = Command::new(&self.program);
+        command.args(options.command_args(query));
+        run(command, input, limits, cancel)
+    }
+}
+
+/// Run a jq-like `command`, feeding `input` on stdin, and return its stdout,
+/// or its stderr as a `QueryError::Jq` if it fails
+///
+/// Limits and cancellation are enforced by killing the process. stdin is
+/// written and stdout/stderr are drained on helper threads so that large
+/// inputs or outputs can't deadlock on full pipe buffers.
+pub(crate) fn run(
+    mut command: Command,
+    input: &InputData,
+    limits: &ExecutionLimits,
+    cancel: &CancelToken,
+) -> Result<String, QueryError> {
+    let started = Instant::now();
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            QueryError::Process(format!("Failed to spawn {}: {}", command.get_program().to_string_lossy(), e))
+        })?;
+
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    // Set by the stdout reader once output grows past the limit
+    let output_overflow = AtomicBool::new(false);
+    let max_output_bytes = limits.max_output_bytes;
+
+    thread::scope(|scope| {
+        // Write JSON to jq's stdin. A broken pipe just means jq exited
+        // early (error or kill), which the exit status reports.
+        scope.spawn(move || {
+            if let Some(mut stdin) = stdin {
+                let _ = input.write_to(&mut stdin);
+            }
+        });
+
+        let overflow = &output_overflow;
+        let stdout_reader = scope.spawn(move || {
+            let output = read_pipe(stdout, max_output_bytes);
+            if let (Ok(bytes), Some(max)) = (&output, max_output_bytes)
+                && bytes.len() > max
+            {
+                overflow.store(true, Ordering::Relaxed);
+            }
+            output
+        });
+        let stderr_reader = scope.spawn(move || read_pipe(stderr, None));
+
+        // Wait for jq to finish, enforcing cancellation and limits in between
+        let status = loop {
+            let stop_reason = if cancel.is_cancelled() {
+                Some(QueryError::Cancelled)
+            } else if output_overflow.load(Ordering::Relaxed) {
+                max_output_bytes.map(QueryError::OutputTooLarge)
+            } else {
+                limits
+                    .timeout
+                    .filter(|timeout| started.elapsed() >= *timeout)
+                    .map(QueryError::TimedOut)
+            };
+
+            if let Some(error) = stop_reason {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(error);
+            }
+
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => thread::sleep(WAIT_POLL_INTERVAL),
+                Err(e) => return Err(QueryError::Process(format!("Failed to read jq output: {}", e))),
+            }
+        };
+
+        let stdout = join_reader(stdout_reader)?;
+        let stderr = join_reader(stderr_reader)?;
+
+        // jq may have finished right as the limit was hit
+        if let Some(max) = max_output_bytes
+            && stdout.len() > max
+        {
+            return Err(QueryError::OutputTooLarge(max));
+        }
+
+        if status.success() {
+            Ok(String::from_utf8_lossy(&stdout).to_string())
+        } else {
+            Err(QueryError::Jq(String::from_utf8_lossy(&stderr).to_string()))
+        }
+    })
+}
+
+/// Read a child pipe to completion, or until just past `limit` bytes
+///
+/// Stopping early closes our end of the pipe so a runaway jq can't keep
+/// growing the buffer while it is being killed.
+fn read_pipe(pipe: Option<impl Read>, limit: Option<usize>) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    match (pipe, limit) {
+        (Some(pipe), Some(limit)) => {
+            pipe.take(limit as u64 + 1).read_to_end(&mut buffer)?;
+        }
+        (Some(mut pipe), None) => {
+            pipe.read_to_end(&mut buffer)?;
+        }
+        (None, _) => {}
+    }
+    Ok(buffer)
+}
+
+/// Collect the output of a pipe reader thread
+fn join_reader(reader: ScopedJoinHandle<'_, std::io::Result<Vec<u8>>>) -> Result<Vec<u8>, QueryError> {
+    reader
+        .join()
+        .map_err(|_| QueryError::Process("Failed to read jq output".to_string()))?
+        .map_err(|e| QueryError::Process(format!("Failed to read jq output: {}", e)))
+}
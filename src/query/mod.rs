@@ -1 +1,12 @@
+pub mod ansi;
+pub mod cache;
+pub mod debouncer;
+pub mod engine;
 pub mod executor;
+pub mod jaq;
+pub mod jq_process;
+pub mod options;
+pub mod output;
+pub mod record;
+pub mod version;
+pub mod worker;
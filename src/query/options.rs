@@ -0,0 +1,367 @@
+use std::path::PathBuf;
+
+use crate::error::JiqError;
+use super::output::OutputOptions;
+use crate::input::mode::InputMode;
+
+/// jq command-line options forwarded to every execution
+///
+/// Mirrors jq's own flags so parameterized filters can be built interactively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JqOptions {
+    /// `--arg NAME VALUE`: `$NAME` bound to a string
+    pub named_args: Vec<(String, String)>,
+    /// `--argjson NAME JSON`: `$NAME` bound to a parsed JSON value
+    pub named_json_args: Vec<(String, String)>,
+    /// `--slurpfile NAME FILE`: `$NAME` bound to an array of the file's JSON values
+    pub slurp_files: Vec<(String, PathBuf)>,
+    /// `--rawfile NAME FILE`: `$NAME` bound to the file's contents as a string
+    pub raw_files: Vec<(String, PathBuf)>,
+    /// `--args`: positional strings in `$ARGS.positional`
+    pub positional_args: Vec<String>,
+    /// `--jsonargs`: positional JSON values in `$ARGS.positional`
+    pub positional_json_args: Vec<String>,
+    /// `--slurp`, `--null-input`, `--raw-input`
+    pub input_mode: InputMode,
+    /// `--raw-output`, `--compact-output`, `--tab`, `--sort-keys` and the output format
+    pub output: OutputOptions,
+}
+
+impl JqOptions {
+    /// Check values up front so mistakes are reported once at startup
+    /// instead of as a jq error on every keystroke
+    pub fn validate(&self) -> Result<(), JiqError> {
+        for (name, value) in &self.named_json_args {
+            serde_json::from_str::<serde_json::Value>(value).map_err(|e| {
+                JiqError::InvalidArgument(format!("--argjson {}: invalid JSON: {}", name, e))
+            })?;
+        }
+
+        for value in &self.positional_json_args {
+            serde_json::from_str::<serde_json::Value>(value).map_err(|e| {
+                JiqError::InvalidArgument(format!("--jsonargs {}: invalid JSON: {}", value, e))
+            })?;
+        }
+
+        let files = self
+            .slurp_files
+            .iter()
+            .map(|file| ("--slurpfile", file))
+            .chain(self.raw_files.iter().map(|file| ("--rawfile", file)));
+        for (flag, (name, path)) in files {
+            if !path.is_file() {
+                return Err(JiqError::InvalidArgument(format!(
+                    "{} {}: file not found: {}",
+                    flag,
+                    name,
+                    path.display()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Arguments that go before the filter
+    pub fn args_before_filter(&self) -> Vec<String> {
+        let mut args: Vec<String> = self
+            .input_mode
+            .jq_flags()
+            .into_iter()
+            .chain(self.output.jq_flags())
+            .map(String::from)
+            .collect();
+
+        for (name, value) in &self.named_args {
+            args.extend(["--arg".to_string(), name.clone(), value.clone()]);
+        }
+        for (name, value) in &self.named_json_args {
+            args.extend(["--argjson".to_string(), name.clone(), value.clone()]);
+        }
+        for (name, path) in &self.slurp_files {
+            args.extend(["--slurpfile".to_string(), name.clone(), path.display().to_string()]);
+        }
+        for (name, path) in &self.raw_files {
+            args.extend(["--rawfile".to_string(), name.clone(), path.display().to_string()]);
+        }
+
+        args
+    }
+
+    /// Arguments that go after the filter (`--args`/`--jsonargs` consume
+    /// every remaining positional argument)
+    pub fn args_after_filter(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if !self.positional_args.is_empty() {
+            args.push("--args".to_string());
+            args.extend(self.positional_args.iter().cloned());
+        }
+        if !self.positional_json_args.is_empty() {
+            args.push("--jsonargs".to_string());
+            args.extend(self.positional_json_args.iter().cloned());
+        }
+
+        args
+    }
+
+    /// jq's whole command line after the program name for running `query`:
+    /// colored output unless monochrome, the options, the filter and the
+    /// positional arguments
+    pub fn command_args(&self, query: &str) -> Vec<String> {
+        let color = if self.output.monochrome {
+            "--monochrome-output"
+        } else {
+            "--color-output"
+        };
+        let mut args = vec![color.to_string()];
+        args.extend(self.args_before_filter());
+        args.push(query.to_string());
+        args.extend(self.args_after_filter());
+        args
+    }
+
+    /// Read back a command line made by `command_args`, returning the filter
+    /// and the options (the embedded engine's worker process gets its query
+    /// this way)
+    pub fn from_command_args(args: impl IntoIterator<Item = String>) -> Result<(String, Self), String> {
+        let mut options = Self::default();
+        let mut filter = None;
+        // Set by --args (false) or --jsonargs (true): every argument after
+        // it is a positional value
+        let mut positional_json = None;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match (arg.as_str(), positional_json) {
+                ("--args", _) => positional_json = Some(false),
+                ("--jsonargs", _) => positional_json = Some(true),
+                (_, Some(false)) => options.positional_args.push(arg),
+                (_, Some(true)) => options.positional_json_args.push(arg),
+                ("--color-output", None) => options.output.monochrome = false,
+                ("--monochrome-output", None) => options.output.monochrome = true,
+                ("--slurp", None) => options.input_mode.slurp = true,
+                ("--null-input", None) => options.input_mode.null_input = true,
+                ("--raw-input", None) => options.input_mode.raw_input = true,
+                ("--raw-output", None) => options.output.raw = true,
+                ("--compact-output", None) => options.output.compact = true,
+                ("--tab", None) => options.output.tab = true,
+                ("--sort-keys", None) => options.output.sort_keys = true,
+                ("--arg", None) => options.named_args.push(named_value(&mut args, &arg)?),
+                ("--argjson", None) => options.named_json_args.push(named_value(&mut args, &arg)?),
+                ("--slurpfile", None) => options.slurp_files.push(named_value(&mut args, &arg)?),
+                ("--rawfile", None) => options.raw_files.push(named_value(&mut args, &arg)?),
+                (_, None) if filter.is_none() => filter = Some(arg),
+                (_, None) => return Err(format!("unexpected argument {}", arg)),
+            }
+        }
+
+        let filter = filter.ok_or_else(|| "no filter given".to_string())?;
+        Ok((filter, options))
+    }
+
+    /// Names of the variables these options define (with leading `$`)
+    pub fn variable_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .named_args
+            .iter()
+            .chain(&self.named_json_args)
+            .map(|(name, _)| name)
+            .chain(self.slurp_files.iter().chain(&self.raw_files).map(|(name, _)| name))
+            .map(|name| format!("${}", name))
+            .collect();
+
+        // $ARGS exposes both named and positional arguments
+        if !names.is_empty()
+            || !self.positional_args.is_empty()
+            || !self.positional_json_args.is_empty()
+        {
+            names.push("$ARGS".to_string());
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// The NAME and VALUE following `flag` (e.g. `--arg NAME VALUE`)
+fn named_value<T: From<String>>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<(String, T), String> {
+    match (args.next(), args.next()) {
+        (Some(name), Some(value)) => Ok((name, T::from(value))),
+        _ => Err(format!("{} needs a name and a value", flag)),
+    }
+}
+
+/// Split a flat `[NAME, VALUE, NAME, VALUE, ...]` list (from clap) into pairs
+pub fn pairs<T: From<String>>(flat: Vec<String>) -> Vec<(String, T)> {
+    let mut pairs = Vec::with_capacity(flat.len() / 2);
+    let mut iter = flat.into_iter();
+    while let (Some(name), Some(value)) = (iter.next(), iter.next()) {
+        pairs.push((name, T::from(value)));
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_has_no_args() {
+        let options = JqOptions::default();
+
+        assert!(options.args_before_filter().is_empty());
+        assert!(options.args_after_filter().is_empty());
+        assert!(options.variable_names().is_empty());
+        assert!(options.validate().is_ok());
+    }
+
+    #[test]
+    fn test_named_args_before_filter() {
+        let options = JqOptions {
+            named_args: vec![("env_name".into(), "prod".into())],
+            named_json_args: vec![("limit".into(), "10".into())],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.args_before_filter(),
+            vec!["--arg", "env_name", "prod", "--argjson", "limit", "10"]
+        );
+    }
+
+    #[test]
+    fn test_input_mode_flags_before_filter() {
+        let options = JqOptions {
+            input_mode: InputMode {
+                slurp: true,
+                ..Default::default()
+            },
+            named_args: vec![("a".into(), "b".into())],
+            ..Default::default()
+        };
+
+        assert_eq!(options.args_before_filter(), vec!["--slurp", "--arg", "a", "b"]);
+    }
+
+    #[test]
+    fn test_output_flags_before_filter() {
+        let options = JqOptions {
+            output: OutputOptions {
+                raw: true,
+                compact: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(options.args_before_filter(), vec!["--raw-output", "--compact-output"]);
+    }
+
+    #[test]
+    fn test_positional_args_after_filter() {
+        let options = JqOptions {
+            positional_args: vec!["a".into(), "b".into()],
+            positional_json_args: vec!["1".into()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.args_after_filter(),
+            vec!["--args", "a", "b", "--jsonargs", "1"]
+        );
+    }
+
+    #[test]
+    fn test_command_args_read_back() {
+        let options = JqOptions {
+            named_args: vec![("who".into(), "--tab".into())],
+            named_json_args: vec![("n".into(), "3".into())],
+            slurp_files: vec![("people".into(), PathBuf::from("people.json"))],
+            raw_files: vec![("tmpl".into(), PathBuf::from("t.txt"))],
+            positional_args: vec!["a".into(), "-b".into()],
+            positional_json_args: vec!["1".into()],
+            input_mode: InputMode {
+                slurp: true,
+                raw_input: true,
+                ..Default::default()
+            },
+            output: OutputOptions {
+                raw: true,
+                sort_keys: true,
+                monochrome: true,
+                ..Default::default()
+            },
+        };
+
+        let args = options.command_args(".[] | $who");
+        assert_eq!(args[0], "--monochrome-output");
+        assert_eq!(JqOptions::from_command_args(args), Ok((".[] | $who".to_string(), options)));
+
+        let (filter, options) = JqOptions::from_command_args(JqOptions::default().command_args(".")).unwrap();
+        assert_eq!(filter, ".");
+        assert_eq!(options, JqOptions::default());
+    }
+
+    #[test]
+    fn test_bad_command_args() {
+        let args = |args: &[&str]| JqOptions::from_command_args(args.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(args(&["--arg", "x"]), Err("--arg needs a name and a value".to_string()));
+        assert_eq!(args(&["--tab"]), Err("no filter given".to_string()));
+        assert_eq!(args(&[".", "."]), Err("unexpected argument .".to_string()));
+    }
+
+    #[test]
+    fn test_variable_names() {
+        let options = JqOptions {
+            named_args: vec![("env_name".into(), "prod".into())],
+            raw_files: vec![("tmpl".into(), PathBuf::from("x.txt"))],
+            ..Default::default()
+        };
+
+        assert_eq!(options.variable_names(), vec!["$ARGS", "$env_name", "$tmpl"]);
+    }
+
+    #[test]
+    fn test_positional_only_defines_args_variable() {
+        let options = JqOptions {
+            positional_args: vec!["a".into()],
+            ..Default::default()
+        };
+
+        assert_eq!(options.variable_names(), vec!["$ARGS"]);
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_argjson() {
+        let options = JqOptions {
+            named_json_args: vec![("limit".into(), "{not json".into())],
+            ..Default::default()
+        };
+
+        match options.validate() {
+            Err(JiqError::InvalidArgument(msg)) => assert!(msg.contains("--argjson limit")),
+            other => panic!("Expected InvalidArgument error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_missing_file() {
+        let options = JqOptions {
+            slurp_files: vec![("data".into(), PathBuf::from("tests/fixtures/nonexistent.json"))],
+            ..Default::default()
+        };
+
+        assert!(matches!(options.validate(), Err(JiqError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_pairs() {
+        let flat = vec!["a".to_string(), "1".to_string(), "b".to_string(), "2".to_string()];
+        let pairs: Vec<(String, String)> = pairs(flat);
+
+        assert_eq!(pairs, vec![("a".into(), "1".into()), ("b".into(), "2".into())]);
+    }
+}
@@ -0,0 +1,434 @@
+use std::env;
+use std::fmt;
+
+use clap::ValueEnum;
+use serde_json::Value;
+
+/// How query results are printed (`-r`, `-c`, `--tab`, `-S`, `--output-format`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputOptions {
+    /// `-r`: strings without quotes
+    pub raw: bool,
+    /// `-c`: each value on a single line
+    pub compact: bool,
+    /// `--tab`: indent with tabs
+    pub tab: bool,
+    /// `-S`: object keys in sorted order
+    pub sort_keys: bool,
+    /// `-M`: no colors, for output that isn't shown on a terminal
+    pub monochrome: bool,
+    pub format: OutputFormat,
+}
+
+/// When printed results are colored (`--color`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal and `$NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output going to a terminal (or not)
+    pub fn use_color(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => is_terminal && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// What the results are written as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// jq's own output
+    #[default]
+    Json,
+    /// One YAML document per value
+    Yaml,
+    /// Arrays of objects as rows under a header of their keys
+    Csv,
+    /// Like csv, separated by tabs
+    Tsv,
+}
+
+impl OutputOptions {
+    /// jq flags for this layout; YAML and CSV/TSV are converted from plain JSON
+    pub fn jq_flags(&self) -> Vec<&'static str> {
+        let mut flags = Vec::new();
+        if self.raw {
+            flags.push("--raw-output");
+        }
+        if self.compact {
+            flags.push("--compact-output");
+        }
+        if self.tab {
+            flags.push("--tab");
+        }
+        if self.sort_keys {
+            flags.push("--sort-keys");
+        }
+        flags
+    }
+
+    /// The JSON output to convert to YAML or CSV/TSV
+    pub fn json_source(&self) -> Self {
+        Self {
+            compact: true,
+            sort_keys: self.sort_keys,
+            monochrome: true,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the output is JSON that reads back as the values jq produced,
+    /// keys in their original order, so another filter can run on it
+    pub fn preserves_values(&self) -> bool {
+        self.format == OutputFormat::Json && !self.raw && !self.sort_keys
+    }
+}
+
+/// The active options, e.g. `yaml, sorted keys`, as shown in the Results title
+impl fmt::Display for OutputOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.format != OutputFormat::Json {
+            parts.push(self.format.to_string());
+        }
+        let flags = [
+            (self.raw, "raw"),
+            (self.compact, "compact"),
+            (self.tab, "tab"),
+            (self.sort_keys, "sorted keys"),
+        ];
+        parts.extend(flags.iter().filter(|(on, _)| *on).map(|(_, name)| name.to_string()));
+        f.write_str(&parts.join(", "))
+    }
+}
+
+impl OutputFormat {
+    /// The format after this one, for cycling through them in the TUI
+    pub fn next(self) -> Self {
+        match self {
+            OutputFormat::Json => OutputFormat::Yaml,
+            OutputFormat::Yaml => OutputFormat::Csv,
+            OutputFormat::Csv => OutputFormat::Tsv,
+            OutputFormat::Tsv => OutputFormat::Json,
+        }
+    }
+
+    /// Convert jq's uncolored JSON output to this format
+    pub fn convert(self, json: &str) -> Result<String, String> {
+        let values = serde_json::Deserializer::from_str(json)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Cannot convert output to {}: {}", self, e))?;
+
+        match self {
+            OutputFormat::Json => Ok(json.to_string()),
+            OutputFormat::Yaml => to_yaml(&values),
+            OutputFormat::Csv => to_delimited(values, b','),
+            OutputFormat::Tsv => to_delimited(values, b'\t'),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+        })
+    }
+}
+
+/// The value that starts on line `line` of uncolored JSON output, for copying
+///
+/// On a `"key": value` line that is the value; on a closing `}` or `]` line
+/// it is the whole object or array. Strings are returned without quotes and
+/// arrays and objects as shown, with the indentation of their first line
+/// removed. `None` if the line doesn't start a JSON value (e.g. YAML output).
+pub fn value_at_line(output: &str, line: usize) -> Option<String> {
+    let line_start: usize = output.split_inclusive('\n').take(line).map(str::len).sum();
+    let line_text = output[line_start..].lines().next()?;
+    let mut start = line_start + (line_text.len() - line_text.trim_start().len());
+
+    let rest = &output[start..];
+    if rest.starts_with('}') || rest.starts_with(']') {
+        start = enclosing_open(output, start)?;
+    } else if rest.starts_with('"') {
+        // A key is followed by its value
+        let key_end = start + json_value_len(rest)?;
+        let after_key = output[key_end..].trim_start();
+        if let Some(value) = after_key.strip_prefix(':') {
+            start = output.len() - value.trim_start().len();
+        }
+    }
+
+    let text = &output[start..start + json_value_len(&output[start..])?];
+    if let Ok(Value::String(string)) = serde_json::from_str(text) {
+        return Some(string);
+    }
+
+    // Later lines of a multi-line value are indented as deep as its first line
+    let first_line_start = output[..start].rfind('\n').map_or(0, |newline| newline + 1);
+    let first_line = &output[first_line_start..start];
+    let indent = &first_line[..first_line.len() - first_line.trim_start().len()];
+    let mut lines = text.split('\n');
+    let mut value = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        value.push('\n');
+        value.push_str(line.strip_prefix(indent).unwrap_or(line));
+    }
+    Some(value)
+}
+
+/// Length of the JSON value at the start of `text`
+fn json_value_len(text: &str) -> Option<usize> {
+    let mut values = serde_json::Deserializer::from_str(text).into_iter::<Value>();
+    values.next()?.ok()?;
+    Some(values.byte_offset())
+}
+
+/// Where the array or object that is still open at `position` starts
+fn enclosing_open(output: &str, position: usize) -> Option<usize> {
+    let mut open = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, byte) in output.as_bytes()[..position].iter().enumerate() {
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            _ if in_string => {}
+            b'{' | b'[' => open.push(index),
+            b'}' | b']' => {
+                open.pop()?;
+            }
+            _ => {}
+        }
+    }
+    open.pop()
+}
+
+/// One YAML document per value, separated by `---`
+fn to_yaml(values: &[Value]) -> Result<String, String> {
+    let mut output = String::new();
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            output.push_str("---\n");
+        }
+        let document = serde_yaml::to_string(value).map_err(|e| format!("Cannot write YAML: {}", e))?;
+        output.push_str(&document);
+    }
+    Ok(output)
+}
+
+/// CSV/TSV rows from the output values
+///
+/// An array of objects or arrays contributes one row per element, anything
+/// else is a row by itself. Objects are written under a header of all their
+/// keys in order of appearance; nested arrays and objects become JSON text.
+fn to_delimited(values: Vec<Value>, delimiter: u8) -> Result<String, String> {
+    let rows: Vec<Value> = values
+        .into_iter()
+        .flat_map(|value| match value {
+            Value::Array(items) if !items.is_empty() && items.iter().all(|item| item.is_object() || item.is_array()) => {
+                items
+            }
+            value => vec![value],
+        })
+        .collect();
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_writer(Vec::new());
+    let write_error = |e: csv::Error| format!("Cannot write CSV: {}", e);
+
+    if rows.iter().any(Value::is_object) {
+        if !rows.iter().all(Value::is_object) {
+            return Err("Cannot write CSV: rows mix objects with other values".to_string());
+        }
+
+        let mut header: Vec<&str> = Vec::new();
+        for row in &rows {
+            for key in row.as_object().into_iter().flat_map(|fields| fields.keys()) {
+                if !header.contains(&key.as_str()) {
+                    header.push(key);
+                }
+            }
+        }
+
+        writer.write_record(&header).map_err(write_error)?;
+        for row in &rows {
+            let cells = header.iter().map(|key| cell(row.get(*key).unwrap_or(&Value::Null)));
+            writer.write_record(cells).map_err(write_error)?;
+        }
+    } else {
+        for row in &rows {
+            match row {
+                Value::Array(items) => writer.write_record(items.iter().map(cell)),
+                value => writer.write_record([cell(value)]),
+            }
+            .map_err(write_error)?;
+        }
+    }
+
+    let bytes = writer.into_inner().map_err(|e| format!("Cannot write CSV: {}", e))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// A CSV cell: strings as they are, null empty, containers as JSON text
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jq_flags() {
+        assert!(OutputOptions::default().jq_flags().is_empty());
+
+        let options = OutputOptions {
+            raw: true,
+            compact: true,
+            tab: true,
+            sort_keys: true,
+            monochrome: true,
+            format: OutputFormat::Json,
+        };
+        // Colors are up to the engine
+        assert_eq!(options.jq_flags(), vec!["--raw-output", "--compact-output", "--tab", "--sort-keys"]);
+    }
+
+    #[test]
+    fn test_color_choice() {
+        assert!(ColorChoice::Always.use_color(false));
+        assert!(!ColorChoice::Never.use_color(true));
+        assert!(!ColorChoice::Auto.use_color(false));
+    }
+
+    #[test]
+    fn test_json_source_keeps_only_sorting() {
+        let options = OutputOptions {
+            raw: true,
+            tab: true,
+            sort_keys: true,
+            format: OutputFormat::Csv,
+            ..Default::default()
+        };
+        assert_eq!(options.json_source().jq_flags(), vec!["--compact-output", "--sort-keys"]);
+    }
+
+    #[test]
+    fn test_preserves_values() {
+        let compact = OutputOptions {
+            compact: true,
+            tab: true,
+            ..Default::default()
+        };
+        assert!(compact.preserves_values());
+
+        let raw = OutputOptions { raw: true, ..Default::default() };
+        let sorted = OutputOptions { sort_keys: true, ..Default::default() };
+        let yaml = OutputOptions {
+            format: OutputFormat::Yaml,
+            ..Default::default()
+        };
+        assert!(!raw.preserves_values());
+        assert!(!sorted.preserves_values());
+        assert!(!yaml.preserves_values());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(OutputOptions::default().to_string(), "");
+
+        let options = OutputOptions {
+            raw: true,
+            sort_keys: true,
+            format: OutputFormat::Tsv,
+            ..Default::default()
+        };
+        assert_eq!(options.to_string(), "tsv, raw, sorted keys");
+    }
+
+    #[test]
+    fn test_formats_cycle() {
+        let mut format = OutputFormat::Json;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            format = format.next();
+            seen.push(format);
+        }
+        assert_eq!(seen, vec![OutputFormat::Yaml, OutputFormat::Csv, OutputFormat::Tsv, OutputFormat::Json]);
+    }
+
+    #[test]
+    fn test_yaml_documents() {
+        let yaml = OutputFormat::Yaml.convert("{\"b\":1,\"a\":[true,null]}\n\"x\"\n").unwrap();
+        assert_eq!(yaml, "b: 1\na:\n- true\n- null\n---\nx\n");
+    }
+
+    #[test]
+    fn test_csv_from_array_of_objects() {
+        let json = r#"[{"name":"Alice","age":30},{"name":"Bob, Jr.","tags":["x"]},{"age":null}]"#;
+        let csv = OutputFormat::Csv.convert(json).unwrap();
+        assert_eq!(csv, "name,age,tags\nAlice,30,\n\"Bob, Jr.\",,\"[\"\"x\"\"]\"\n,,\n");
+    }
+
+    #[test]
+    fn test_tsv_from_stream_of_objects() {
+        let tsv = OutputFormat::Tsv.convert("{\"a\":1,\"b\":\"x\"}\n{\"a\":2,\"b\":\"y\"}\n").unwrap();
+        assert_eq!(tsv, "a\tb\n1\tx\n2\ty\n");
+    }
+
+    #[test]
+    fn test_csv_from_arrays_and_scalars() {
+        assert_eq!(OutputFormat::Csv.convert("[[1,\"a\"],[2]]").unwrap(), "1,a\n2\n");
+        assert_eq!(OutputFormat::Csv.convert("\"x\"\n3\n").unwrap(), "x\n3\n");
+    }
+
+    const PRETTY: &str = "{\n  \"name\": \"Alice\",\n  \"tags\": [\n    \"a\",\n    \"}\"\n  ],\n  \"n\": 1.50\n}\n";
+
+    #[test]
+    fn test_value_at_line() {
+        assert_eq!(value_at_line(PRETTY, 1).as_deref(), Some("Alice"));
+        assert_eq!(value_at_line(PRETTY, 2).as_deref(), Some("[\n  \"a\",\n  \"}\"\n]"));
+        assert_eq!(value_at_line(PRETTY, 4).as_deref(), Some("}"));
+        assert_eq!(value_at_line(PRETTY, 6).as_deref(), Some("1.50"));
+        assert_eq!(value_at_line(PRETTY, 8), None);
+    }
+
+    #[test]
+    fn test_value_at_closing_line_is_the_container() {
+        assert_eq!(value_at_line(PRETTY, 5), value_at_line(PRETTY, 2));
+        assert_eq!(value_at_line(PRETTY, 7), value_at_line(PRETTY, 0));
+        assert!(value_at_line(PRETTY, 0).unwrap().ends_with("\"n\": 1.50\n}"));
+    }
+
+    #[test]
+    fn test_value_at_line_of_other_output() {
+        assert_eq!(value_at_line("{\"a\":{\"b\":2}}\n[1]\n", 1).as_deref(), Some("[1]"));
+        assert_eq!(value_at_line("name: Alice\n", 0), None);
+        assert_eq!(value_at_line("plain text\n", 0), None);
+    }
+
+    #[test]
+    fn test_csv_rejects_mixed_rows() {
+        let error = OutputFormat::Csv.convert("[{\"a\":1},[2]]").unwrap_err();
+        assert!(error.contains("mix objects"));
+    }
+}
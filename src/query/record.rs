@@ -0,0 +1,474 @@
+use std::borrow::Cow;
+
+use clap::ValueEnum;
+use serde_json::{Value, json};
+
+use crate::input::delimited::CsvOptions;
+use crate::input::format::InputFormat;
+use crate::input::source::InputOrigin;
+use super::options::JqOptions;
+
+/// How a query record is printed (`--record`, `:record`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RecordFormat {
+    /// A JSON object with the query, jq options, inputs and result
+    #[default]
+    Json,
+    /// A jq command line reproducing the result, followed by the result as comments
+    Shell,
+}
+
+/// A query with what it takes to reproduce its result
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryRecord<'a> {
+    pub query: &'a str,
+    pub options: &'a JqOptions,
+    /// How the inputs were read, empty for plain JSON from stdin
+    pub inputs: &'a [InputOrigin],
+    /// The uncolored output, or the error message
+    ///
+    /// For a shell record this must be jq's JSON output, as the command
+    /// line prints it, whatever the output format.
+    pub result: Result<String, String>,
+}
+
+impl QueryRecord<'_> {
+    /// The record as text ending with a newline
+    pub fn format(&self, format: RecordFormat) -> Result<String, String> {
+        match format {
+            RecordFormat::Json => Ok(self.to_json()),
+            RecordFormat::Shell => self.to_shell(),
+        }
+    }
+
+    /// Shell command line running the query with jq on the same input
+    ///
+    /// Compressed input is piped through its decompressor and CSV/TSV through
+    /// a jq conversion. Fails for input a jq command can't reproduce: YAML,
+    /// TOML, CSV with quoted fields and JSON that `--lenient` had to fix.
+    pub fn command_line(&self) -> Result<String, String> {
+        let origins = self.origins();
+        let converters = origins.iter().map(input_command).collect::<Result<Vec<_>, _>>()?;
+
+        if converters.iter().all(Option::is_none) {
+            // jq reads every file (or stdin) itself
+            let files: Vec<String> = origins
+                .iter()
+                .filter_map(|origin| origin.path.as_ref())
+                .map(|path| path.display().to_string())
+                .collect();
+            return join(self.jq_words(&files));
+        }
+
+        let mut producers = Vec::with_capacity(origins.len());
+        for (origin, converter) in origins.iter().zip(converters) {
+            producers.push(match (converter, &origin.path) {
+                (Some(converter), _) => converter,
+                (None, Some(path)) => join(["cat".to_string(), path.display().to_string()])?,
+                (None, None) => "cat".to_string(),
+            });
+        }
+        let input = match producers.as_slice() {
+            [producer] => producer.clone(),
+            producers => format!("{{ {}; }}", producers.join("; ")),
+        };
+        Ok(format!("{} | {}", input, join(self.jq_words(&[]))?))
+    }
+
+    /// The query as jq runs it (empty means identity)
+    fn filter(&self) -> &str {
+        match self.query.trim() {
+            "" => ".",
+            query => query,
+        }
+    }
+
+    /// The inputs, stdin read as plain JSON if none were recorded
+    fn origins(&self) -> Cow<'_, [InputOrigin]> {
+        if self.inputs.is_empty() {
+            let stdin = InputOrigin {
+                format: InputFormat::Json,
+                ..Default::default()
+            };
+            Cow::Owned(vec![stdin])
+        } else {
+            Cow::Borrowed(self.inputs)
+        }
+    }
+
+    /// `jq`, its options, the filter and `files`, then `--args`/`--jsonargs`
+    /// (which take every argument after them)
+    fn jq_words(&self, files: &[String]) -> Vec<String> {
+        let mut words = vec!["jq".to_string()];
+        words.extend(self.options.args_before_filter());
+        words.push(self.filter().to_string());
+        words.extend(files.iter().cloned());
+        words.extend(self.options.args_after_filter());
+        words
+    }
+
+    fn to_json(&self) -> String {
+        let mut options = self.options.args_before_filter();
+        options.extend(self.options.args_after_filter());
+        let inputs: Vec<Value> = self.origins().iter().map(origin_json).collect();
+
+        let mut record = json!({
+            "query": self.filter(),
+            "options": options,
+            "format": self.options.output.format.to_string(),
+            "inputs": inputs,
+        });
+        match &self.result {
+            Ok(output) => record["result"] = json!(output),
+            Err(error) => record["error"] = json!(error),
+        }
+
+        let mut text = serde_json::to_string_pretty(&record).unwrap_or_default();
+        text.push('\n');
+        text
+    }
+
+    /// The command line; the result follows as `#` comments so the whole
+    /// record can be pasted into a script
+    fn to_shell(&self) -> Result<String, String> {
+        let mut text = self
+            .command_line()
+            .map_err(|e| format!("No jq command line reproduces this run: {} (use --record json)", e))?;
+        text.push('\n');
+
+        match &self.result {
+            Ok(output) => {
+                for line in output.lines() {
+                    text.push_str(format!("# {}", line).trim_end());
+                    text.push('\n');
+                }
+            }
+            Err(error) => {
+                for line in error.lines() {
+                    text.push_str(&format!("# error: {}\n", line));
+                }
+            }
+        }
+        Ok(text)
+    }
+}
+
+/// How one input was read, for the JSON record
+fn origin_json(origin: &InputOrigin) -> Value {
+    let mut fields = json!({
+        "path": origin.path.as_ref().map(|path| path.display().to_string()),
+        "compression": origin.compression.map(|compression| compression.to_string()),
+        "format": origin.format.to_string(),
+        "lenient": origin.lenient.is_some(),
+    });
+    if let Some(csv) = &origin.csv {
+        let character = |byte: Option<u8>| byte.map(|byte| (byte as char).to_string());
+        fields["csv"] = json!({
+            "delimiter": character(csv.delimiter),
+            "header": csv.has_headers,
+            "quote": character(csv.quote),
+            "infer_types": csv.infer_types,
+        });
+    }
+    fields
+}
+
+/// Shell command writing `origin` to stdout as JSON jq can read, `None` if
+/// jq can read it as it is
+fn input_command(origin: &InputOrigin) -> Result<Option<String>, String> {
+    let name = origin.path.as_ref().map_or("stdin".to_string(), |path| path.display().to_string());
+    let file: Vec<String> = origin.path.iter().map(|path| path.display().to_string()).collect();
+    let decompress = match origin.compression {
+        Some(compression) => {
+            let command = compression.decompress_command().map(String::from);
+            Some(join(command.into_iter().chain(file.iter().cloned()))?)
+        }
+        None => None,
+    };
+
+    match origin.format {
+        InputFormat::Auto | InputFormat::Json => {
+            if origin.lenient.as_ref().is_some_and(|changes| changes.summary().is_some()) {
+                return Err(format!("{} is only valid JSON after --lenient fixed it", name));
+            }
+            Ok(decompress)
+        }
+        InputFormat::Csv | InputFormat::Tsv => {
+            if origin.quoted {
+                return Err(format!("{} has quoted fields, which jq can't split", name));
+            }
+            let default_delimiter = if origin.format == InputFormat::Csv { b',' } else { b'\t' };
+            let csv = origin.csv.clone().unwrap_or_default();
+            let filter = csv_filter(csv.delimiter.unwrap_or(default_delimiter), &csv);
+            let convert = ["jq", "-R", "-s", "-c", &filter].map(String::from);
+
+            Ok(Some(match decompress {
+                Some(decompress) => format!("{} | {}", decompress, join(convert)?),
+                None => join(convert.into_iter().chain(file))?,
+            }))
+        }
+        format => Err(format!("jq can't read {} input ({})", format, name)),
+    }
+}
+
+/// jq filter turning raw CSV/TSV text (`jq -R -s`) without quoted fields into
+/// the array jiq converts it to
+fn csv_filter(delimiter: u8, options: &CsvOptions) -> String {
+    let delimiter = json!((delimiter as char).to_string());
+    let cell = if options.infer_types {
+        r#"if . == "" then null elif . == "true" then true elif . == "false" then false elif test("^-?(0|[1-9][0-9]*)(\\.[0-9]+)?([eE][-+]?[0-9]+)?$") then tonumber else . end"#
+    } else {
+        "."
+    };
+
+    let rows = format!(r#"split("\n") | map(sub("\r$"; "") | select(length > 0) | split({}))"#, delimiter);
+    if options.has_headers {
+        format!(
+            "{} | .[0] as $header | .[1:] | map(map({}) | [$header, .] | transpose | map({{(.[0]): .[1]}}) | add)",
+            rows, cell
+        )
+    } else {
+        format!("{} | map(map({}))", rows, cell)
+    }
+}
+
+/// Shell-quote and join words into a command line
+fn join(words: impl IntoIterator<Item = String>) -> Result<String, String> {
+    let words: Vec<String> = words.into_iter().collect();
+    shlex::try_join(words.iter().map(String::as_str)).map_err(|e| format!("cannot quote the command line: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::compression::Compression;
+    use crate::input::delimited;
+    use crate::input::lenient::Normalization;
+    use crate::query::output::{OutputFormat, OutputOptions};
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn options() -> JqOptions {
+        JqOptions {
+            named_args: vec![("who".to_string(), "O'Brien".to_string())],
+            positional_args: vec!["x".to_string()],
+            output: OutputOptions {
+                raw: true,
+                format: OutputFormat::Csv,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn file(path: &str) -> InputOrigin {
+        InputOrigin {
+            path: Some(PathBuf::from(path)),
+            format: InputFormat::Json,
+            ..Default::default()
+        }
+    }
+
+    fn record<'a>(query: &'a str, options: &'a JqOptions, inputs: &'a [InputOrigin]) -> QueryRecord<'a> {
+        QueryRecord {
+            query,
+            options,
+            inputs,
+            result: Ok(String::new()),
+        }
+    }
+
+    /// Run a command line with sh, returning its stdout
+    fn sh(command: &str) -> String {
+        let output = Command::new("sh").args(["-c", command]).output().unwrap();
+        assert!(output.status.success(), "{}: {}", command, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_command_line_argument_order() {
+        let options = options();
+        let inputs = [file("data.json")];
+        assert_eq!(
+            record(".a", &options, &inputs).command_line().unwrap(),
+            "jq --raw-output --arg who \"O'Brien\" .a data.json --args x"
+        );
+    }
+
+    #[test]
+    fn test_shell_record_quotes_arguments() {
+        let options = options();
+        let inputs = [file("my data.json")];
+        let record = QueryRecord {
+            result: Ok("1\n\n2\n".to_string()),
+            ..record(".users[] | select(.name == $who) | \"\\(.id)\"", &options, &inputs)
+        };
+        let shell = record.format(RecordFormat::Shell).unwrap();
+
+        let mut lines = shell.lines();
+        let command = lines.next().unwrap();
+        assert_eq!(lines.collect::<Vec<_>>(), vec!["# 1", "#", "# 2"]);
+
+        // The shell gives jq exactly the arguments
+        let words = shlex::split(command).unwrap();
+        assert_eq!(words[..5], ["jq", "--raw-output", "--arg", "who", "O'Brien"]);
+        assert_eq!(words[5..], [record.query, "my data.json", "--args", "x"]);
+    }
+
+    #[test]
+    fn test_shell_record_from_stdin_with_error() {
+        let options = JqOptions::default();
+        let record = QueryRecord {
+            result: Err("jq: error: boom".to_string()),
+            ..record("", &options, &[])
+        };
+        assert_eq!(record.format(RecordFormat::Shell).unwrap(), "jq .\n# error: jq: error: boom\n");
+    }
+
+    #[test]
+    fn test_compressed_inputs_are_piped_through_decompressor() {
+        let options = JqOptions::default();
+        let inputs = [InputOrigin {
+            compression: Some(Compression::Gzip),
+            ..file("data.jsonl.gz")
+        }];
+        assert_eq!(record(".a", &options, &inputs).command_line().unwrap(), "gzip -dc data.jsonl.gz | jq .a");
+
+        let stdin = [InputOrigin {
+            path: None,
+            compression: Some(Compression::Xz),
+            ..file("")
+        }];
+        assert_eq!(record(".a", &options, &stdin).command_line().unwrap(), "xz -dc | jq .a");
+
+        let mixed = [file("a.json"), inputs[0].clone()];
+        assert_eq!(
+            record(".a", &options, &mixed).command_line().unwrap(),
+            "{ cat a.json; gzip -dc data.jsonl.gz; } | jq .a"
+        );
+    }
+
+    #[test]
+    fn test_unreproducible_inputs_are_refused() {
+        let options = JqOptions::default();
+        let yaml = [InputOrigin {
+            format: InputFormat::Yaml,
+            ..file("compose.yml")
+        }];
+        assert_eq!(
+            record(".", &options, &yaml).command_line(),
+            Err("jq can't read yaml input (compose.yml)".to_string())
+        );
+
+        let quoted = [InputOrigin {
+            format: InputFormat::Csv,
+            csv: Some(CsvOptions::default()),
+            quoted: true,
+            ..file("people.csv")
+        }];
+        assert!(record(".", &options, &quoted).command_line().unwrap_err().contains("quoted fields"));
+
+        let lenient = [InputOrigin {
+            lenient: Some(Normalization {
+                comments: 1,
+                ..Default::default()
+            }),
+            ..file("tsconfig.json")
+        }];
+        let shell = record(".", &options, &lenient).format(RecordFormat::Shell).unwrap_err();
+        assert!(shell.starts_with("No jq command line reproduces this run: tsconfig.json"));
+
+        // --lenient on input that needed no fixing is plain JSON
+        let strict = [InputOrigin {
+            lenient: Some(Normalization::default()),
+            ..file("strict.json")
+        }];
+        assert_eq!(record(".", &options, &strict).command_line().unwrap(), "jq . strict.json");
+    }
+
+    #[test]
+    fn test_csv_command_converts_like_jiq() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = "id;name;score\r\n1;Alice;9.5\r\n\r\n2;Bob;\r\n007;true;-1.5e-1\r\n";
+        let path = dir.path().join("scores.csv");
+        std::fs::write(&path, csv).unwrap();
+
+        for (has_headers, infer_types) in [(true, false), (true, true), (false, true)] {
+            let options = CsvOptions {
+                delimiter: Some(b';'),
+                has_headers,
+                infer_types,
+                ..Default::default()
+            };
+            let inputs = [InputOrigin {
+                format: InputFormat::Csv,
+                csv: Some(options.clone()),
+                ..file(path.to_str().unwrap())
+            }];
+            let jq_options = JqOptions::default();
+            let command = record(".", &jq_options, &inputs).command_line().unwrap();
+
+            let converted: Value = serde_json::from_str(&sh(&command)).unwrap();
+            let expected: Value =
+                serde_json::from_str(&delimited::delimited_to_json(csv, b',', &options).unwrap()).unwrap();
+            assert_eq!(converted, expected, "{}", command);
+        }
+    }
+
+    #[test]
+    fn test_json_record() {
+        let options = options();
+        let inputs = [
+            file("a.json"),
+            InputOrigin {
+                compression: Some(Compression::Gzip),
+                format: InputFormat::Tsv,
+                csv: Some(CsvOptions {
+                    quote: None,
+                    infer_types: true,
+                    ..Default::default()
+                }),
+                ..file("b.tsv.gz")
+            },
+        ];
+        let record = QueryRecord {
+            result: Ok("Alice\n".to_string()),
+            ..record(" .name ", &options, &inputs)
+        };
+        let json: Value = serde_json::from_str(&record.format(RecordFormat::Json).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "query": ".name",
+                "options": ["--raw-output", "--arg", "who", "O'Brien", "--args", "x"],
+                "format": "csv",
+                "inputs": [
+                    {"path": "a.json", "compression": null, "format": "json", "lenient": false},
+                    {
+                        "path": "b.tsv.gz",
+                        "compression": "gzip",
+                        "format": "tsv",
+                        "lenient": false,
+                        "csv": {"delimiter": null, "header": true, "quote": null, "infer_types": true},
+                    },
+                ],
+                "result": "Alice\n",
+            })
+        );
+        let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["query", "options", "format", "inputs", "result"]);
+    }
+
+    #[test]
+    fn test_json_record_with_error() {
+        let options = JqOptions::default();
+        let record = QueryRecord {
+            result: Err("boom".to_string()),
+            ..record(".a", &options, &[])
+        };
+        let json: Value = serde_json::from_str(&record.format(RecordFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["error"], "boom");
+        assert_eq!(json["inputs"], json!([{"path": null, "compression": null, "format": "json", "lenient": false}]));
+        assert!(json.get("result").is_none());
+    }
+}
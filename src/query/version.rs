@@ -0,0 +1,95 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+/// A jq release, as reported by `jq --version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JqVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl JqVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// Parse `jq --version` output such as `jq-1.7.1`, `jq-1.6` or `jq-1.5rc2-dirty`
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix("jq-").or_else(|| text.strip_prefix("jq "))?;
+
+        let mut parts = text.split('.').map(|part| {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u32>().ok()
+        });
+        let major = parts.next()??;
+        let minor = parts.next().flatten().unwrap_or(0);
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(Self::new(major, minor, patch))
+    }
+
+    /// Run `program --version`; `None` if it fails or prints something unexpected
+    pub fn detect(program: &Path) -> Option<Self> {
+        let output = Command::new(program).arg("--version").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// `1.7.1`, or `1.6` for a release without a patch number
+impl fmt::Display for JqVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.patch == 0 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_release_versions() {
+        assert_eq!(JqVersion::parse("jq-1.7.1\n"), Some(JqVersion::new(1, 7, 1)));
+        assert_eq!(JqVersion::parse("jq-1.6"), Some(JqVersion::new(1, 6, 0)));
+        assert_eq!(JqVersion::parse("jq-1.8.0"), Some(JqVersion::new(1, 8, 0)));
+    }
+
+    #[test]
+    fn test_parse_development_versions() {
+        assert_eq!(JqVersion::parse("jq-1.5rc2-dirty"), Some(JqVersion::new(1, 5, 0)));
+        assert_eq!(JqVersion::parse("jq-1.7.1-12-gabcdef"), Some(JqVersion::new(1, 7, 1)));
+    }
+
+    #[test]
+    fn test_parse_rejects_other_output() {
+        assert_eq!(JqVersion::parse("gojq 0.12.13"), None);
+        assert_eq!(JqVersion::parse(""), None);
+        assert_eq!(JqVersion::parse("jq-master"), None);
+    }
+
+    #[test]
+    fn test_versions_are_ordered() {
+        assert!(JqVersion::new(1, 6, 0) < JqVersion::new(1, 7, 0));
+        assert!(JqVersion::new(1, 7, 0) < JqVersion::new(1, 7, 1));
+        assert!(JqVersion::new(1, 10, 0) > JqVersion::new(1, 9, 5));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(JqVersion::new(1, 7, 1).to_string(), "1.7.1");
+        assert_eq!(JqVersion::new(1, 6, 0).to_string(), "1.6");
+    }
+
+    #[test]
+    fn test_detect_missing_program() {
+        assert_eq!(JqVersion::detect(Path::new("/nonexistent/jq")), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use super::executor::{CancelToken, JqExecutor};
+use crate::error::QueryError;
+
+/// A query running on a background thread
+struct InFlightQuery {
+    cancel: CancelToken,
+    receiver: Receiver<Result<String, QueryError>>,
+}
+
+/// Runs jq queries off the UI thread
+///
+/// Only the most recently submitted query is tracked. Submitting a new query
+/// kills the jq process of the previous one, so stale results never arrive.
+pub struct QueryWorker {
+    executor: Arc<JqExecutor>,
+    in_flight: Option<InFlightQuery>,
+}
+
+impl QueryWorker {
+    /// Create a worker that executes queries with the given executor
+    pub fn new(executor: JqExecutor) -> Self {
+        Self {
+            executor: Arc::new(executor),
+            in_flight: None,
+        }
+    }
+
+    /// Start executing a query in the background, cancelling any in-flight query
+    pub fn submit(&mut self, query: &str) {
+        self.cancel();
+
+        let cancel = CancelToken::new();
+        let (sender, receiver) = mpsc::channel();
+        let executor = Arc::clone(&self.executor);
+        let query = query.to_string();
+        let thread_cancel = cancel.clone();
+
+        thread::spawn(move || {
+            let result = executor.execute_cancellable(&query, &thread_cancel);
+            if !thread_cancel.is_cancelled() {
+                // Receiver may already be gone if the app is shutting down
+                let _ = sender.send(result);
+            }
+        });
+
+        self.in_flight = Some(InFlightQuery { cancel, receiver });
+    }
+
+    /// Check for a finished result without blocking
+    ///
+    /// Returns `None` if nothing is running or the query hasn't finished yet.
+    pub fn try_recv(&mut self) -> Option<Result<String, QueryError>> {
+        let in_flight = self.in_flight.as_ref()?;
+
+        match in_flight.receiver.try_recv() {
+            Ok(result) => {
+                self.in_flight = None;
+                Some(result)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                // Worker thread died without sending a result
+                self.in_flight = None;
+                Some(Err(QueryError::Process(
+                    "Query worker stopped unexpectedly".to_string(),
+                )))
+            }
+        }
+    }
+
+    /// Check if a query is currently running
+    pub fn is_running(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    /// Cancel the in-flight query (if any), killing its jq process
+    pub fn cancel(&mut self) {
+        if let Some(in_flight) = self.in_flight.take() {
+            in_flight.cancel.cancel();
+        }
+    }
+}
+
+impl Drop for QueryWorker {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    // Block until the worker produces a result (or give up after a few seconds)
+    fn wait_for_result(worker: &mut QueryWorker) -> Option<Result<String, QueryError>> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(result) = worker.try_recv() {
+                return Some(result);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        None
+    }
+
+    #[test]
+    fn test_submit_returns_result() {
+        let mut worker = QueryWorker::new(JqExecutor::new(r#"{"name": "Alice"}"#.to_string()));
+
+        worker.submit(".name");
+        assert!(worker.is_running());
+
+        let result = wait_for_result(&mut worker).expect("query should finish");
+        assert!(result.unwrap().contains("Alice"));
+        assert!(!worker.is_running());
+    }
+
+    #[test]
+    fn test_try_recv_when_idle_returns_none() {
+        let mut worker = QueryWorker::new(JqExecutor::new("{}".to_string()));
+
+        assert!(!worker.is_running());
+        assert!(worker.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_new_submit_replaces_stale_query() {
+        let mut worker = QueryWorker::new(JqExecutor::new(r#"{"a": 1, "b": 2}"#.to_string()));
+
+        // Never terminates on its own; must be killed by the next submit
+        worker.submit("repeat(.)");
+        worker.submit(".b");
+
+        let result = wait_for_result(&mut worker).expect("query should finish");
+        assert!(result.unwrap().contains('2'));
+    }
+
+    #[test]
+    fn test_cancel_stops_running_query() {
+        let mut worker = QueryWorker::new(JqExecutor::new("{}".to_string()));
+
+        worker.submit("repeat(.)");
+        worker.cancel();
+
+        assert!(!worker.is_running());
+        assert!(worker.try_recv().is_none());
+    }
+}
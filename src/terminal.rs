@@ -0,0 +1,70 @@
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Stdout, Write};
+
+/// The TUI's terminal, drawn on `TerminalOutput`
+pub type Tui = Terminal<CrosstermBackend<TerminalOutput>>;
+
+/// Where the TUI is drawn: stdout, or the controlling terminal when stdout is
+/// redirected (`jiq data.json > out.json`), so stdout only gets the exit output
+pub enum TerminalOutput {
+    Stdout(Stdout),
+    Tty(File),
+}
+
+impl TerminalOutput {
+    /// Open the terminal the user is sitting at
+    pub fn open() -> io::Result<Self> {
+        if io::stdout().is_terminal() {
+            Ok(TerminalOutput::Stdout(io::stdout()))
+        } else {
+            OpenOptions::new().write(true).open("/dev/tty").map(TerminalOutput::Tty)
+        }
+    }
+}
+
+impl Write for TerminalOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TerminalOutput::Stdout(stdout) => stdout.write(buf),
+            TerminalOutput::Tty(tty) => tty.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TerminalOutput::Stdout(stdout) => stdout.flush(),
+            TerminalOutput::Tty(tty) => tty.flush(),
+        }
+    }
+}
+
+/// Enter raw mode and the alternate screen, like `ratatui::init` but on
+/// `TerminalOutput`; a panic restores the terminal before it is reported
+pub fn init() -> io::Result<Tui> {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        hook(info);
+    }));
+
+    let mut output = TerminalOutput::open()?;
+    enable_raw_mode()?;
+    execute!(output, EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(output))
+}
+
+/// Leave raw mode and the alternate screen, ignoring errors (nothing is left to
+/// report them to)
+pub fn restore() {
+    // Raw mode first: it has more side effects than the alternate screen
+    let _ = disable_raw_mode();
+    if let Ok(mut output) = TerminalOutput::open() {
+        let _ = execute!(output, LeaveAlternateScreen);
+    }
+}
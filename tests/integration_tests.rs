@@ -22,6 +22,465 @@ fn test_cli_with_invalid_json_file() {
         .stderr(predicate::str::contains("Invalid JSON"));
 }
 
+#[test]
+fn test_cli_with_invalid_ndjson_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.jsonl");
+    fs::write(&path, "{\"a\": 1}\n{\"a\": 2}\n{\"a\" 3}\n").unwrap();
+
+    cargo_bin_cmd!()
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("record 3"))
+        .stderr(predicate::str::contains("line 3"));
+}
+
+#[test]
+fn test_cli_with_invalid_yaml_reports_source_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.yaml");
+    fs::write(&path, "a: 1\nb: [1, 2\nc: 3\n").unwrap();
+
+    cargo_bin_cmd!()
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid YAML"))
+        .stderr(predicate::str::contains("line 3"));
+}
+
+#[test]
+fn test_cli_decompresses_stdin() {
+    use std::io::Write;
+
+    // Invalid JSON so jiq exits before starting the TUI; the error proves
+    // the gzip stream was decompressed and parsed
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(b"{\"a\": }").unwrap();
+
+    cargo_bin_cmd!()
+        .write_stdin(encoder.finish().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid JSON"))
+        .stderr(predicate::str::contains("line 1"));
+}
+
+#[test]
+fn test_cli_names_invalid_file_among_several() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .arg(fixture_path("invalid.json"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid.json"))
+        .stderr(predicate::str::contains("Invalid JSON"));
+}
+
+#[test]
+fn test_cli_with_unmatched_glob() {
+    let dir = tempfile::tempdir().unwrap();
+
+    cargo_bin_cmd!()
+        .arg(dir.path().join("*.json"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no files match"));
+}
+
+#[test]
+fn test_cli_follow_rejects_input_files() {
+    cargo_bin_cmd!()
+        .arg("--follow")
+        .arg(fixture_path("simple.json"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_cli_invalid_json_shows_offending_line() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("invalid.json"))
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("line 3 column 20"))
+        .stderr(predicate::str::contains("byte offset"))
+        .stderr(predicate::str::contains("3 |   \"missing_quote: \"value\","))
+        .stderr(predicate::str::contains("^"));
+}
+
+#[test]
+fn test_cli_validate_accepts_valid_files() {
+    cargo_bin_cmd!()
+        .arg("--validate")
+        .arg(fixture_path("simple.json"))
+        .arg(fixture_path("simple.yaml"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("simple.json: OK"))
+        .stdout(predicate::str::contains("simple.yaml: OK"));
+}
+
+#[test]
+fn test_cli_validate_reports_every_invalid_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.jsonl");
+    fs::write(&path, "{\"a\": 1}\n{\"a\" 2}\n").unwrap();
+
+    cargo_bin_cmd!()
+        .arg("--validate")
+        .arg(fixture_path("invalid.json"))
+        .arg(fixture_path("simple.json"))
+        .arg(&path)
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("simple.json: OK"))
+        .stderr(predicate::str::contains("invalid.json: Invalid JSON input"))
+        .stderr(predicate::str::contains("broken.jsonl: Invalid JSON input: record 2"))
+        .stderr(predicate::str::contains("2 | {\"a\" 2}"));
+}
+
+#[test]
+fn test_cli_validate_reads_stdin() {
+    cargo_bin_cmd!()
+        .arg("--validate")
+        .write_stdin("{\"a\": [1, 2,]}")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("stdin: Invalid JSON input"));
+}
+
+#[test]
+fn test_cli_expr_prints_result() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("array.json"))
+        .args(["-e", "map(.name) | join(\",\")"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Alice,Bob,Charlie"));
+}
+
+#[test]
+fn test_cli_no_tui_reads_stdin_with_identity() {
+    cargo_bin_cmd!()
+        .arg("--no-tui")
+        .write_stdin("{\"a\": [1, 2]}")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"a\""));
+}
+
+#[test]
+fn test_cli_expr_uses_input_handling() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.yaml"))
+        .args(["-e", "keys | length"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("3"));
+
+    cargo_bin_cmd!()
+        .arg(fixture_path("tsconfig.json"))
+        .args(["--lenient", "-e", "type"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("object"));
+}
+
+#[test]
+fn test_cli_expr_query_error_exits_5() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["-e", ".name | error"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("Error:"));
+}
+
+#[test]
+fn test_cli_expr_with_embedded_engine() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--engine", "jaq", "--arg", "who", "x", "-e", "[.name, $who]"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"Alice\""))
+        .stdout(predicate::str::contains("\"x\""));
+
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--engine", "jaq", "-e", ".name | error"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("jaq: error"));
+}
+
+#[test]
+fn test_cli_expr_invalid_input_exits_2() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("invalid.json"))
+        .args(["-e", "."])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid JSON"));
+}
+
+#[test]
+fn test_cli_expr_unmatched_glob_exits_2() {
+    let dir = tempfile::tempdir().unwrap();
+
+    cargo_bin_cmd!()
+        .arg(dir.path().join("*.json"))
+        .args(["-e", "."])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::starts_with("Error: Invalid argument "))
+        .stderr(predicate::str::ends_with("*.json: no files match\n"));
+
+    cargo_bin_cmd!()
+        .arg("--no-tui")
+        .args(["--slurpfile", "x", &dir.path().join("missing.json").to_string_lossy()])
+        .write_stdin("{}")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("file not found"))
+        .stderr(predicate::str::contains("Location").not());
+}
+
+#[test]
+fn test_cli_expr_rejects_tabs() {
+    cargo_bin_cmd!()
+        .arg("--tabs")
+        .arg(fixture_path("simple.json"))
+        .args(["-e", "."])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_cli_raw_output() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("array.json"))
+        .args(["-r", "-e", ".[].name"])
+        .assert()
+        .success()
+        .stdout("Alice\nBob\nCharlie\n");
+}
+
+#[test]
+fn test_cli_csv_output() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("array.json"))
+        .args(["--output-format", "csv", "-e", "."])
+        .assert()
+        .success()
+        .stdout("id,name,active\n1,Alice,true\n2,Bob,false\n3,Charlie,true\n");
+}
+
+#[test]
+fn test_cli_yaml_output_with_sorted_keys() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--output-format", "yaml", "-S", "-e", "."])
+        .assert()
+        .success()
+        .stdout("age: 30\ncity: Seattle\nname: Alice\n");
+}
+
+#[test]
+fn test_cli_csv_output_rejects_mixed_rows() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--output-format", "tsv", "-e", "., [1]"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("Cannot write CSV"));
+}
+
+#[test]
+fn test_cli_output_to_pipe_is_uncolored() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["-c", "-e", "."])
+        .assert()
+        .success()
+        .stdout("{\"name\":\"Alice\",\"age\":30,\"city\":\"Seattle\"}\n");
+}
+
+/// Run the TUI on a pseudo-terminal (via `script`) with stdout redirected to
+/// a file, exit with Enter and return the file's contents
+#[cfg(unix)]
+fn tui_output_redirected(input: &std::path::Path) -> Option<String> {
+    use std::io::{Read, Write};
+    use std::process::{Command, Stdio};
+
+    // `script` gives the TUI a terminal; without it there is nothing to test
+    which::which("script").ok()?;
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("out.json");
+    let command = format!(
+        "timeout --foreground 10 {} {} > {}",
+        shlex::try_quote(env!("CARGO_BIN_EXE_jiq")).unwrap(),
+        shlex::try_quote(input.to_str().unwrap()).unwrap(),
+        shlex::try_quote(out.to_str().unwrap()).unwrap(),
+    );
+    let mut child = Command::new("script")
+        .args(["-qec", &command, "/dev/null"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Press Enter once the TUI has taken over the terminal
+    let mut screen = child.stdout.take().unwrap();
+    let mut seen = Vec::new();
+    let mut buf = [0; 4096];
+    while !seen.windows(8).any(|w| w == b"\x1b[?1049h") {
+        let n = screen.read(&mut buf).unwrap();
+        assert!(n > 0, "TUI never started: {}", String::from_utf8_lossy(&seen));
+        seen.extend_from_slice(&buf[..n]);
+    }
+    // Keep stdin open until jiq exits, `script` may end the session on EOF
+    let mut keys = child.stdin.take().unwrap();
+    keys.write_all(b"\r").unwrap();
+    keys.flush().unwrap();
+    std::thread::spawn(move || std::io::copy(&mut screen, &mut std::io::sink()));
+    assert!(child.wait().unwrap().success());
+    drop(keys);
+
+    Some(fs::read_to_string(&out).unwrap())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_cli_tui_with_redirected_stdout_writes_only_json() {
+    let Some(output) = tui_output_redirected(&fixture_path("simple.json")) else {
+        return;
+    };
+    assert_eq!(output, "{\n  \"name\": \"Alice\",\n  \"age\": 30,\n  \"city\": \"Seattle\"\n}\n");
+}
+
+#[test]
+fn test_cli_color_always() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--color", "always", "-e", ".name"])
+        .assert()
+        .success()
+        .stdout("\x1b[0;32m\"Alice\"\x1b[0m\n");
+}
+
+#[test]
+fn test_cli_raw_output_keeps_escape_sequences_from_data() {
+    cargo_bin_cmd!()
+        .args(["--color", "never", "-r", "-e", ".msg"])
+        .write_stdin(r#"{"msg": "\u001b[31mred\u001b[0m"}"#)
+        .assert()
+        .success()
+        .stdout("\x1b[31mred\x1b[0m\n");
+}
+
+#[test]
+fn test_cli_shell_record() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("array.json"))
+        .args(["--record", "shell", "-r", "--arg", "who", "Bob's", "-e", ".[0].name + \" & \" + $who"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "jq --raw-output --arg who \"Bob's\" '.[0].name + \" & \" + $who' ",
+        ))
+        .stdout(predicate::str::ends_with("array.json\n# Alice & Bob's\n"));
+}
+
+#[test]
+fn test_cli_json_record() {
+    let output = cargo_bin_cmd!()
+        .args(["--record", "json", "-c", "-e", ".a"])
+        .write_stdin("{\"a\": [1, 2]}")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let record: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        record,
+        serde_json::json!({
+            "query": ".a",
+            "options": ["--compact-output"],
+            "format": "json",
+            "inputs": [{"path": null, "compression": null, "format": "json", "lenient": false}],
+            "result": "[1,2]\n",
+        })
+    );
+}
+
+#[test]
+fn test_cli_shell_record_reproduces_converted_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let gz = dir.path().join("events.jsonl.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, &fs::read(fixture_path("events.jsonl")).unwrap()).unwrap();
+    fs::write(&gz, encoder.finish().unwrap()).unwrap();
+    let csv = dir.path().join("scores.csv");
+    fs::write(&csv, "name,score\nAlice,9.5\nBob,7\n").unwrap();
+
+    // The result is shown as jq prints it, whatever the output format
+    let output = cargo_bin_cmd!()
+        .arg(&gz)
+        .arg(&csv)
+        .args(["--infer-types", "--output-format", "yaml", "--record", "shell", "-c"])
+        .args(["-e", "if type == \"array\" then map(.score) else .level end"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let record = String::from_utf8(output).unwrap();
+    let (command, comments) = record.split_once('\n').unwrap();
+    assert!(command.starts_with("{ gzip -dc "), "{}", command);
+
+    let ran = std::process::Command::new("sh").args(["-c", command]).output().unwrap();
+    assert!(ran.status.success(), "{}", String::from_utf8_lossy(&ran.stderr));
+    let expected: String = comments.lines().map(|line| format!("{}\n", line.trim_start_matches("# "))).collect();
+    assert_eq!(String::from_utf8(ran.stdout).unwrap(), expected);
+    assert!(expected.contains("\"warn\"") && expected.contains("[9.5,7]"));
+}
+
+#[test]
+fn test_cli_shell_record_refuses_unreproducible_input() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("people.csv"))
+        .args(["--record", "shell", "-e", "."])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("people.csv has quoted fields"))
+        .stderr(predicate::str::contains("--record json"));
+
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.yaml"))
+        .args(["--record", "shell", "-e", "."])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("jq can't read yaml input"));
+}
+
+#[test]
+fn test_cli_record_of_failed_query_exits_5() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--record", "json", "-e", ".name | error"])
+        .assert()
+        .code(5)
+        .stdout(predicate::str::contains("\"error\": "));
+}
+
 #[test]
 fn test_cli_with_nonexistent_file() {
     cargo_bin_cmd!()
@@ -30,6 +489,58 @@ fn test_cli_with_nonexistent_file() {
         .failure();
 }
 
+#[test]
+fn test_cli_with_invalid_argjson() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--argjson", "limit", "{not json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--argjson limit"));
+}
+
+#[test]
+fn test_cli_missing_jq_suggests_embedded_engine() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--engine", "jq"])
+        .env("PATH", "")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--engine jaq"));
+}
+
+#[test]
+fn test_cli_jq_path_is_used() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--engine", "jq", "--jq-path", "/nonexistent/jq-1.7"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("jq binary not found: /nonexistent/jq-1.7"));
+}
+
+#[test]
+fn test_cli_jq_path_from_environment() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--engine", "jq"])
+        .env("JIQ_JQ", "/nonexistent/jq-1.6")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("jq binary not found: /nonexistent/jq-1.6"));
+}
+
+#[test]
+fn test_cli_rejects_unknown_engine() {
+    cargo_bin_cmd!()
+        .arg(fixture_path("simple.json"))
+        .args(["--engine", "gojq"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("possible values: auto, jq, jaq"));
+}
+
 #[test]
 fn test_cli_help_flag() {
     cargo_bin_cmd!()